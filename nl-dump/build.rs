@@ -1,7 +1,10 @@
 use std::env;
-use std::path::PathBuf;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 
 extern crate rust_enum_derive;
+extern crate toml;
 
 fn main() {
     let output_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
@@ -15,4 +18,273 @@ fn main() {
         }
         Ok(_) => { }
     }
+
+    let mut attr_types = Vec::new();
+    find_attr_types(&input_dir, &mut attr_types);
+    let mut path = output_dir.clone();
+    path.push("attr_types.rs");
+    let r = write_attr_types(&path, &attr_types);
+    if let Err(e) = r {
+        panic!("Error: {}", e);
+    }
+}
+
+/// The [attr-types] table of one .toml: what the payload of each attribute
+/// of the enum `name` is.
+struct AttrTypes {
+    name: String,
+    types: Vec<(String, String)>,
+    selects: Vec<Select>,
+}
+
+/// An attribute whose payload depends on a string attribute next to it, eg.
+/// IFLA_INFO_DATA on IFLA_INFO_KIND, or the name of an enum in one, eg.
+/// RTA_ENCAP on RTA_ENCAP_TYPE. From "select(IFLA_INFO_KIND, table)"
+/// and the [table] of payload types by string. With "select(family, table)"
+/// the table is by the address family of the message instead. "_" in the
+/// table is the type for everything else.
+struct Select {
+    attr: String,
+    by: String,
+    types: Vec<(String, String)>,
+}
+
+fn find_attr_types(dir: &Path, v: &mut Vec<AttrTypes>) {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir).unwrap()
+                                                   .map(|e| e.unwrap().path())
+                                                   .collect();
+    // so that the output doesn't change from one build to the next
+    entries.sort();
+    for path in entries {
+        if fs::metadata(&path).unwrap().is_dir() {
+            find_attr_types(&path, v);
+            continue;
+        }
+        if path.extension().map(|e| e.to_string_lossy().to_lowercase()) != Some("toml".to_string()) {
+            continue;
+        }
+        let mut s = String::new();
+        File::open(&path).unwrap().read_to_string(&mut s).unwrap();
+        let table = match toml::Parser::new(&s).parse() {
+            Some(t) => t,
+            None => panic!("failed to parse {}", path.display()),
+        };
+        let types = match table.get("attr-types").and_then(|t| t.as_table()) {
+            Some(t) => t,
+            None => continue,
+        };
+        let name = table.get("rust-enum-derive").and_then(|t| t.as_table())
+                        .and_then(|t| t.get("name")).and_then(|n| n.as_str());
+        let name = match name {
+            Some(n) => n,
+            None => panic!("{} has attr-types but no name", path.display()),
+        };
+        let mut a = AttrTypes { name: name.to_string(), types: Vec::new(), selects: Vec::new() };
+        for (attr, t) in types.iter() {
+            let t = match t.as_str() {
+                Some(t) => t,
+                None => panic!("{}: the type of {} isn't a string", path.display(), attr),
+            };
+            let (by, select_table) = match split_type(t) {
+                Some(("select", args)) => match args.find(',') {
+                    Some(i) => (args[..i].trim(), args[i+1..].trim()),
+                    None => panic!("{}: select for {} needs an attribute and a table",
+                                   path.display(), attr),
+                },
+                _ => {
+                    a.types.push((attr.clone(), t.to_string()));
+                    continue;
+                }
+            };
+            let select_types = match table.get(select_table).and_then(|t| t.as_table()) {
+                Some(t) => t,
+                None => panic!("{}: there is no [{}] for {}", path.display(), select_table, attr),
+            };
+            let mut select = Select { attr: attr.clone(), by: by.to_string(), types: Vec::new() };
+            for (k, t) in select_types.iter() {
+                match t.as_str() {
+                    Some(t) => select.types.push((k.clone(), t.to_string())),
+                    None => panic!("{}: the type of {} isn't a string", path.display(), k),
+                }
+            }
+            a.selects.push(select);
+        }
+        v.push(a);
+    }
+}
+
+/// "nested(IflaInfo)" -> Some(("nested", "IflaInfo"))
+fn split_type(t: &str) -> Option<(&str, &str)> {
+    match (t.find('('), t.ends_with(')')) {
+        (Some(i), true) => Some((&t[..i], &t[i+1..t.len()-1])),
+        _ => None,
+    }
+}
+
+fn payload_type(t: &str) -> Option<String> {
+    let s = match t {
+        "bytes" => "PayloadType::Bytes".to_string(),
+        "u8" => "PayloadType::U8".to_string(),
+        "u16" => "PayloadType::U16".to_string(),
+        "u32" => "PayloadType::U32".to_string(),
+        "u64" => "PayloadType::U64".to_string(),
+        "s32" => "PayloadType::S32".to_string(),
+        "be16" => "PayloadType::Be16".to_string(),
+        "be32" => "PayloadType::Be32".to_string(),
+        "be64" => "PayloadType::Be64".to_string(),
+        "string" => "PayloadType::String".to_string(),
+        "mac" => "PayloadType::Mac".to_string(),
+        "ip" => "PayloadType::Ip".to_string(),
+        "hex" => "PayloadType::Hex".to_string(),
+        "flag" => "PayloadType::Flag".to_string(),
+        "u16s" => "PayloadType::U16s".to_string(),
+        "u32s" => "PayloadType::U32s".to_string(),
+        "u64s" => "PayloadType::U64s".to_string(),
+        "nexthops" => "PayloadType::Nexthops".to_string(),
+        "mpls" => "PayloadType::Mpls".to_string(),
+        "handle" => "PayloadType::Handle".to_string(),
+        _ => match split_type(t) {
+            Some(("u8", e)) => format!("PayloadType::Enum(1, name_{})", e),
+            Some(("u16", e)) => format!("PayloadType::Enum(2, name_{})", e),
+            Some(("u32", e)) => format!("PayloadType::Enum(4, name_{})", e),
+            Some(("be16", e)) => format!("PayloadType::BeEnum(2, name_{})", e),
+            Some(("flags", e)) => format!("PayloadType::Enum(4, flags_{})", e),
+            Some(("s32s", names)) => format!("PayloadType::S32s({})", names),
+            Some(("nested", e)) => format!("PayloadType::Nested(read_nested_{})", e),
+            Some(("list", e)) => format!("PayloadType::Nested(read_list_{})", e),
+            Some(("struct", e)) => format!("PayloadType::Struct(read_struct_{})", e),
+            _ => return None,
+        },
+    };
+    Some(s)
+}
+
+/// Write the AttrType impls, and the Nested enum with a variant for every
+/// enum that is nested in something.
+fn write_attr_types(path: &Path, attr_types: &[AttrTypes]) -> std::io::Result<()> {
+    let mut nested: Vec<&str> = Vec::new();
+    let mut lists: Vec<&str> = Vec::new();
+    let mut structs: Vec<&str> = Vec::new();
+    let mut enums: Vec<&str> = Vec::new();
+    let mut flags: Vec<&str> = Vec::new();
+    for a in attr_types {
+        let selected = a.selects.iter().flat_map(|s| s.types.iter());
+        for &(_, ref t) in a.types.iter().chain(selected) {
+            match split_type(t) {
+                Some(("nested", e)) => if !nested.contains(&e) { nested.push(e) },
+                // each one in the list is read like nested(E)
+                Some(("list", e)) => {
+                    if !lists.contains(&e) { lists.push(e) }
+                    if !nested.contains(&e) { nested.push(e) }
+                }
+                Some(("struct", e)) => if !structs.contains(&e) { structs.push(e) },
+                Some(("s32s", _)) => (),
+                Some(("flags", e)) => if !flags.contains(&e) { flags.push(e) },
+                Some((_, e)) => if !enums.contains(&e) { enums.push(e) },
+                _ => (),
+            }
+        }
+    }
+    nested.sort();
+    lists.sort();
+    structs.sort();
+    enums.sort();
+    flags.sort();
+
+    let mut f = try!(File::create(path));
+    try!(write!(f, "/// The attributes inside an attribute, by what type they are\n"));
+    try!(write!(f, "#[derive(Debug, Clone)]\npub enum Nested {{\n"));
+    try!(write!(f, "    /// NLA_F_NESTED was set, but we don't know what the types are\n"));
+    try!(write!(f, "    Raw(Vec<Rtattr<u16>>),\n"));
+    for e in nested.iter() {
+        try!(write!(f, "    {}(Vec<Rtattr<{}>>),\n", e, e));
+    }
+    try!(write!(f, "}}\nimpl Nested {{\n"));
+    try!(write!(f, "    pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {{\n"));
+    try!(write!(f, "        match *self {{\n"));
+    try!(write!(f, "            Nested::Raw(ref v) => fmt_attrs(f, v, indent),\n"));
+    for e in nested.iter() {
+        try!(write!(f, "            Nested::{}(ref v) => fmt_attrs(f, v, indent),\n", e));
+    }
+    try!(write!(f, "        }}\n    }}\n}}\n\n"));
+
+    for e in nested.iter() {
+        try!(write!(f, "#[allow(non_snake_case)]\n"));
+        try!(write!(f, "fn read_nested_{}(cursor: &mut Cursor<&[u8]>, end: usize,\n", e));
+        try!(write!(f, "                   family: Value<AddressFamily>) -> error::Result<Nested> {{\n"));
+        try!(write!(f, "    read_attrs(cursor, end, family).map(Nested::{})\n}}\n", e));
+    }
+    for e in lists.iter() {
+        try!(write!(f, "#[allow(non_snake_case)]\n"));
+        try!(write!(f, "fn read_list_{}(cursor: &mut Cursor<&[u8]>, end: usize,\n", e));
+        try!(write!(f, "                 family: Value<AddressFamily>) -> error::Result<Nested> {{\n"));
+        try!(write!(f, "    read_list(cursor, end, family, read_nested_{}).map(Nested::Raw)\n}}\n", e));
+    }
+    for s in structs.iter() {
+        try!(write!(f, "#[allow(non_snake_case)]\n"));
+        try!(write!(f, "fn read_struct_{}(cursor: &mut Cursor<&[u8]>, end: usize)\n", s));
+        try!(write!(f, "                   -> error::Result<Box<AttrStruct>> {{\n"));
+        try!(write!(f, "    {}::read(cursor, end).map(|s| Box::new(s) as Box<AttrStruct>)\n}}\n", s));
+    }
+
+    for e in enums.iter() {
+        try!(write!(f, "#[allow(non_snake_case)]\n"));
+        try!(write!(f, "fn name_{}(n: u32) -> String {{\n", e));
+        try!(write!(f, "    Value::<{}>::new(n).to_string()\n}}\n", e));
+    }
+    for e in flags.iter() {
+        try!(write!(f, "#[allow(non_snake_case)]\n"));
+        try!(write!(f, "fn flags_{}(n: u32) -> String {{\n", e));
+        try!(write!(f, "    format!(\"{{:#X}} ({{}})\", n, Flags::<{}>::new(n))\n}}\n", e));
+    }
+
+    for a in attr_types {
+        try!(write!(f, "\nimpl AttrType for {} {{\n", a.name));
+        try!(write!(f, "    fn payload_type(rta_type: &Value<{}>) -> PayloadType {{\n", a.name));
+        try!(write!(f, "        match *rta_type {{\n"));
+        for &(ref attr, ref t) in a.types.iter() {
+            let p = match payload_type(t) {
+                Some(p) => p,
+                None => panic!("{}: unknown attribute type \"{}\" for {}", a.name, t, attr),
+            };
+            try!(write!(f, "            Value::Known({}::{}) => {},\n", a.name, attr, p));
+        }
+        try!(write!(f, "            _ => PayloadType::Bytes,\n"));
+        try!(write!(f, "        }}\n    }}\n"));
+        if !a.selects.is_empty() {
+            // a table only uses one of prev and family
+            try!(write!(f, "    #[allow(unused_variables)]\n"));
+            try!(write!(f, "    fn payload_type_after(rta_type: &Value<{}>, prev: &[Rtattr<{}>],\n", a.name, a.name));
+            try!(write!(f, "                          family: Value<AddressFamily>) -> PayloadType {{\n"));
+            try!(write!(f, "        match *rta_type {{\n"));
+            for s in a.selects.iter() {
+                try!(write!(f, "            Value::Known({}::{}) => {{\n", a.name, s.attr));
+                if s.by == "family" {
+                    try!(write!(f, "                match family {{\n"));
+                } else {
+                    try!(write!(f, "                match string_attr(prev, {}::{}) {{\n", a.name, s.by));
+                }
+                let mut default = "PayloadType::Bytes".to_string();
+                for &(ref k, ref t) in s.types.iter() {
+                    let p = match payload_type(t) {
+                        Some(p) => p,
+                        None => panic!("{}: unknown attribute type \"{}\" for {}", a.name, t, k),
+                    };
+                    if k == "_" {
+                        default = p;
+                    } else if s.by == "family" {
+                        try!(write!(f, "                    Value::Known(AddressFamily::{}) => {},\n", k, p));
+                    } else {
+                        try!(write!(f, "                    Some(\"{}\") => {},\n", k, p));
+                    }
+                }
+                try!(write!(f, "                    _ => {},\n", default));
+                try!(write!(f, "                }}\n            }}\n"));
+            }
+            try!(write!(f, "            _ => {}::payload_type(rta_type),\n", a.name));
+            try!(write!(f, "        }}\n    }}\n"));
+        }
+        try!(write!(f, "}}\n"));
+    }
+    Ok(())
 }
@@ -0,0 +1,642 @@
+/* A small reader for pcap and pcapng files. libpcap refuses pcapng files
+ * where the interfaces don't all share one link type, which is exactly what
+ * you get from capturing nlmon0 and eth0 together, so we read them ourselves.
+ */
+
+use ::std::io;
+use ::std::io::prelude::*;
+use ::std::io::Cursor;
+use ::std::fs::File;
+use ::byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+
+const PCAP_MAGIC_USEC: u32 = 0xA1B2C3D4;
+const PCAP_MAGIC_NSEC: u32 = 0xA1B23C4D;
+const PCAPNG_SHB: u32 = 0x0A0D0D0A;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B3C4D;
+const PCAPNG_IDB: u32 = 1;
+const PCAPNG_OPB: u32 = 2;
+const PCAPNG_SPB: u32 = 3;
+const PCAPNG_EPB: u32 = 6;
+const PCAPNG_OPT_ENDOFOPT: u16 = 0;
+const PCAPNG_OPT_IF_TSRESOL: u16 = 9;
+const SNAPLEN: u32 = 262144;
+// what an empty pcap file claims to hold
+const LINKTYPE_NETLINK: u32 = 253;
+// anything bigger than this is a corrupt file, not a packet
+const MAX_BLOCK_SIZE: u32 = 16 * 1024 * 1024;
+
+/// One captured packet
+#[derive(Debug, Default, Clone)]
+pub struct Record {
+    pub interface: u32,
+    pub link_type: u32,
+    pub ts_sec: u64,
+    pub ts_nsec: u32,
+    pub orig_len: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Endian {
+    Little,
+    Big,
+}
+
+fn read_u16(cursor: &mut Cursor<&[u8]>, e: Endian) -> io::Result<u16> {
+    match e {
+        Endian::Little => Ok(try!(cursor.read_u16::<LittleEndian>())),
+        Endian::Big => Ok(try!(cursor.read_u16::<BigEndian>())),
+    }
+}
+
+fn read_u32(cursor: &mut Cursor<&[u8]>, e: Endian) -> io::Result<u32> {
+    match e {
+        Endian::Little => Ok(try!(cursor.read_u32::<LittleEndian>())),
+        Endian::Big => Ok(try!(cursor.read_u32::<BigEndian>())),
+    }
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "truncated record")
+}
+
+/// Running off the end of a block that was read in full is a bad block, not
+/// the end of the input like running off the end of the file is. The block is
+/// in memory, so any error that isn't already InvalidData is running off it.
+fn short_block(e: io::Error, block_type: u32) -> io::Error {
+    if e.kind() == io::ErrorKind::InvalidData {
+        e
+    }
+    else {
+        io::Error::new(io::ErrorKind::InvalidData,
+                       format!("pcapng block {:#X} is too short: {}", block_type, e))
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Interface {
+    link_type: u32,
+    // timestamp units per second
+    ts_units: u64,
+}
+
+enum Format {
+    Pcap { endian: Endian, nsec: bool, link_type: u32 },
+    Pcapng { endian: Endian, interfaces: Vec<Interface> },
+}
+
+pub struct CapReader {
+    r: Box<Read>,
+    format: Format,
+}
+impl CapReader {
+    /// Read the file header and figure out whether this is pcap or pcapng.
+    pub fn new(mut r: Box<Read>) -> io::Result<CapReader> {
+        let mut magic = [0u8; 4];
+        try!(r.read_exact(&mut magic));
+        let mut c = Cursor::new(&magic as &[u8]);
+        let le = try!(c.read_u32::<LittleEndian>());
+
+        if le == PCAPNG_SHB {
+            let mut len = [0u8; 4];
+            try!(r.read_exact(&mut len));
+            let mut cr = CapReader { r: r,
+                                     format: Format::Pcapng { endian: Endian::Little,
+                                                              interfaces: Vec::new() } };
+            try!(cr.read_shb(len));
+            return Ok(cr);
+        }
+
+        let (endian, nsec) = match le {
+            PCAP_MAGIC_USEC => (Endian::Little, false),
+            PCAP_MAGIC_NSEC => (Endian::Little, true),
+            _ => match le.swap_bytes() {
+                PCAP_MAGIC_USEC => (Endian::Big, false),
+                PCAP_MAGIC_NSEC => (Endian::Big, true),
+                _ => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                              format!("not a pcap or pcapng file, magic = {:#X}", le)));
+                }
+            },
+        };
+        // version_major, version_minor, thiszone, sigfigs, snaplen, network
+        let mut hdr = [0u8; 20];
+        try!(r.read_exact(&mut hdr));
+        let mut c = Cursor::new(&hdr as &[u8]);
+        c.set_position(16);
+        let link_type = try!(read_u32(&mut c, endian));
+
+        Ok(CapReader { r: r,
+                       format: Format::Pcap { endian: endian, nsec: nsec, link_type: link_type } })
+    }
+    /// The next packet, or None at the end of the file. A record that was
+    /// cut off at the end of the file (eg. a capture that is still being
+    /// written) is also treated as the end. A pcapng block that is too short
+    /// for what it should hold is an InvalidData error, and the next call
+    /// carries on with the block after it.
+    pub fn next(&mut self) -> io::Result<Option<Record>> {
+        let r = match self.format {
+            Format::Pcap { .. } => self.next_pcap(),
+            Format::Pcapng { .. } => self.next_pcapng(),
+        };
+        match r {
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                info!("CapReader::next() end of input: {}", e);
+                Ok(None)
+            }
+            _ => r,
+        }
+    }
+    /// Fill buf, returning false if we were already at the end of the input.
+    fn read_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let mut got = 0;
+        while got < buf.len() {
+            match self.r.read(&mut buf[got..]) {
+                Ok(0) => {
+                    if got == 0 {
+                        return Ok(false);
+                    }
+                    return Err(truncated());
+                }
+                Ok(n) => got += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(true)
+    }
+    fn next_pcap(&mut self) -> io::Result<Option<Record>> {
+        let (endian, nsec, link_type) = match self.format {
+            Format::Pcap { endian, nsec, link_type } => (endian, nsec, link_type),
+            _ => unreachable!(),
+        };
+        let mut hdr = [0u8; 16];
+        if !try!(self.read_or_eof(&mut hdr)) {
+            return Ok(None);
+        }
+        let mut c = Cursor::new(&hdr as &[u8]);
+        let mut rec = Record::default();
+        rec.link_type = link_type;
+        rec.ts_sec = try!(read_u32(&mut c, endian)) as u64;
+        let frac = try!(read_u32(&mut c, endian));
+        rec.ts_nsec = if nsec { frac } else { frac.saturating_mul(1000) };
+        let incl_len = try!(read_u32(&mut c, endian));
+        rec.orig_len = try!(read_u32(&mut c, endian));
+        if incl_len > MAX_BLOCK_SIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      format!("incl_len = {}", incl_len)));
+        }
+        rec.data = vec![0u8; incl_len as usize];
+        if !try!(self.read_or_eof(&mut rec.data)) && incl_len > 0 {
+            return Err(truncated());
+        }
+        Ok(Some(rec))
+    }
+    /// Read one whole pcapng block, returning its type and body.
+    fn read_block(&mut self) -> io::Result<Option<(u32, Vec<u8>)>> {
+        let endian = match self.format {
+            Format::Pcapng { endian, .. } => endian,
+            _ => unreachable!(),
+        };
+        let mut hdr = [0u8; 8];
+        if !try!(self.read_or_eof(&mut hdr)) {
+            return Ok(None);
+        }
+        let mut c = Cursor::new(&hdr as &[u8]);
+        let block_type = try!(read_u32(&mut c, endian));
+        if block_type == PCAPNG_SHB {
+            // a new section, which might have a different byte order
+            let mut len = [0u8; 4];
+            len.copy_from_slice(&hdr[4..]);
+            try!(self.read_shb(len));
+            return Ok(Some((PCAPNG_SHB, Vec::new())));
+        }
+        let total_len = try!(read_u32(&mut c, endian));
+        if total_len < 12 || total_len > MAX_BLOCK_SIZE || total_len % 4 != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      format!("pcapng block_total_length = {}", total_len)));
+        }
+        // the body plus the trailing copy of total_len
+        let mut body = vec![0u8; (total_len - 8) as usize];
+        if !try!(self.read_or_eof(&mut body)) {
+            return Err(truncated());
+        }
+        let len = body.len();
+        body.truncate(len - 4);
+        Ok(Some((block_type, body)))
+    }
+    /// Read a section header block. The block type and the length have
+    /// already been read, but the length comes before we know the byte order
+    /// so it is still raw.
+    fn read_shb(&mut self, len: [u8; 4]) -> io::Result<()> {
+        let mut bom = [0u8; 4];
+        try!(self.r.read_exact(&mut bom));
+        let mut c = Cursor::new(&bom as &[u8]);
+        let bom = try!(c.read_u32::<LittleEndian>());
+        let endian = if bom == PCAPNG_BYTE_ORDER_MAGIC {
+            Endian::Little
+        }
+        else if bom.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC {
+            Endian::Big
+        }
+        else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      format!("pcapng byte_order_magic = {:#X}", bom)));
+        };
+        let mut c = Cursor::new(&len as &[u8]);
+        let total_len = try!(read_u32(&mut c, endian));
+        if total_len < 28 || total_len > MAX_BLOCK_SIZE || total_len % 4 != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      format!("pcapng SHB length = {}", total_len)));
+        }
+        // version, section length, options and the trailing length
+        let mut rest = vec![0u8; (total_len - 12) as usize];
+        try!(self.r.read_exact(&mut rest));
+        // interface ids are per section
+        self.format = Format::Pcapng { endian: endian, interfaces: Vec::new() };
+        Ok(())
+    }
+    fn next_pcapng(&mut self) -> io::Result<Option<Record>> {
+        loop {
+            let (block_type, body) = match try!(self.read_block()) {
+                Some(b) => b,
+                None => return Ok(None),
+            };
+            let endian = match self.format {
+                Format::Pcapng { endian, .. } => endian,
+                _ => unreachable!(),
+            };
+            match block_type {
+                PCAPNG_IDB => {
+                    let i = try!(CapReader::parse_idb(&body, endian)
+                                 .map_err(|e| short_block(e, block_type)));
+                    debug!("pcapng interface {:?}", i);
+                    if let Format::Pcapng { ref mut interfaces, .. } = self.format {
+                        interfaces.push(i);
+                    }
+                }
+                PCAPNG_EPB | PCAPNG_OPB | PCAPNG_SPB => {
+                    let rec = try!(self.parse_packet(block_type, &body, endian)
+                                   .map_err(|e| short_block(e, block_type)));
+                    return Ok(Some(rec));
+                }
+                // section headers, statistics, name resolution, etc
+                _ => continue,
+            }
+        }
+    }
+    /// The packet in an enhanced, obsolete or simple packet block body
+    fn parse_packet(&self, block_type: u32, body: &[u8], endian: Endian) -> io::Result<Record> {
+        let mut c = Cursor::new(body);
+        let mut rec = Record::default();
+        if block_type == PCAPNG_SPB {
+            rec.orig_len = try!(read_u32(&mut c, endian));
+            let caplen = ::std::cmp::min(rec.orig_len as usize, body.len() - 4);
+            rec.data = body[4 .. 4 + caplen].to_vec();
+            let i = try!(self.interface(0));
+            rec.link_type = i.link_type;
+            return Ok(rec);
+        }
+        let if_id = if block_type == PCAPNG_EPB {
+            try!(read_u32(&mut c, endian))
+        }
+        else {
+            let id = try!(read_u16(&mut c, endian)) as u32;
+            let _drops_count = try!(read_u16(&mut c, endian));
+            id
+        };
+        let ts_high = try!(read_u32(&mut c, endian)) as u64;
+        let ts_low = try!(read_u32(&mut c, endian)) as u64;
+        let caplen = try!(read_u32(&mut c, endian)) as usize;
+        rec.orig_len = try!(read_u32(&mut c, endian));
+        let start = c.position() as usize;
+        if start + caplen > body.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      format!("pcapng caplen = {}", caplen)));
+        }
+        rec.data = body[start .. start + caplen].to_vec();
+        let i = try!(self.interface(if_id));
+        rec.interface = if_id;
+        rec.link_type = i.link_type;
+        let ts = (ts_high << 32) | ts_low;
+        rec.ts_sec = ts / i.ts_units;
+        rec.ts_nsec = CapReader::to_nsec(ts % i.ts_units, i.ts_units);
+        Ok(rec)
+    }
+    /// Convert a fraction of a second in ts_units to nanoseconds
+    fn to_nsec(frac: u64, ts_units: u64) -> u32 {
+        if ts_units <= ::std::u64::MAX / 1000000000 {
+            (frac * 1000000000 / ts_units) as u32
+        }
+        else {
+            (frac as f64 * 1000000000.0 / ts_units as f64) as u32
+        }
+    }
+    fn interface(&self, if_id: u32) -> io::Result<Interface> {
+        match self.format {
+            Format::Pcapng { ref interfaces, .. } => {
+                match interfaces.get(if_id as usize) {
+                    Some(i) => Ok(*i),
+                    None => Err(io::Error::new(io::ErrorKind::InvalidData,
+                                               format!("packet for unknown interface {}", if_id))),
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    fn parse_idb(body: &[u8], endian: Endian) -> io::Result<Interface> {
+        let mut c = Cursor::new(body);
+        let link_type = try!(read_u16(&mut c, endian)) as u32;
+        let _reserved = try!(read_u16(&mut c, endian));
+        let _snaplen = try!(read_u32(&mut c, endian));
+        let mut i = Interface { link_type: link_type, ts_units: 1000000 };
+
+        while (c.position() as usize) + 4 <= body.len() {
+            let code = try!(read_u16(&mut c, endian));
+            let len = try!(read_u16(&mut c, endian)) as u64;
+            if code == PCAPNG_OPT_ENDOFOPT {
+                break;
+            }
+            let value_pos = c.position();
+            if code == PCAPNG_OPT_IF_TSRESOL && len == 1 {
+                let v = try!(c.read_u8());
+                // the high bit says whether this is a power of 2 or 10
+                let exp = (v & 0x7F) as u32;
+                if v & 0x80 == 0 && exp <= 19 {
+                    i.ts_units = 10u64.pow(exp);
+                }
+                else if v & 0x80 != 0 && exp <= 63 {
+                    i.ts_units = 1u64 << exp;
+                }
+            }
+            // options are padded to 32 bits
+            c.set_position(value_pos + ((len + 3) & !3));
+        }
+        Ok(i)
+    }
+}
+
+enum WriteFormat {
+    // the link type comes from the first packet written
+    Pcap { link_type: Option<u32> },
+    // (input interface, link type) for each interface we have described
+    Pcapng { interfaces: Vec<(u32, u32)> },
+}
+
+/// Writes Records back out with their original timestamps and link types.
+/// pcap files only have one link type, so packets with a different one are
+/// skipped. pcapng files get an interface for each one.
+pub struct CapWriter {
+    w: Box<Write>,
+    format: WriteFormat,
+}
+impl CapWriter {
+    /// Create a pcapng file if the path ends with .pcapng, otherwise pcap.
+    pub fn create(path: &str) -> io::Result<CapWriter> {
+        let f = try!(File::create(path));
+        let w = Box::new(io::BufWriter::new(f));
+        CapWriter::new(w, path.ends_with(".pcapng"))
+    }
+    pub fn new(mut w: Box<Write>, pcapng: bool) -> io::Result<CapWriter> {
+        let format = if pcapng {
+            let mut body: Vec<u8> = Vec::new();
+            try!(body.write_u32::<LittleEndian>(PCAPNG_BYTE_ORDER_MAGIC));
+            try!(body.write_u16::<LittleEndian>(1)); // major_version
+            try!(body.write_u16::<LittleEndian>(0)); // minor_version
+            try!(body.write_i64::<LittleEndian>(-1)); // section_length
+            try!(CapWriter::write_block(&mut w, PCAPNG_SHB, &body));
+            WriteFormat::Pcapng { interfaces: Vec::new() }
+        }
+        else {
+            WriteFormat::Pcap { link_type: None }
+        };
+        Ok(CapWriter { w: w, format: format })
+    }
+    /// Write one packet. Returns false if it was skipped because it can't be
+    /// represented in this file.
+    pub fn write(&mut self, rec: &Record) -> io::Result<bool> {
+        match self.format {
+            WriteFormat::Pcap { ref mut link_type } => {
+                match *link_type {
+                    Some(l) if l != rec.link_type => return Ok(false),
+                    Some(_) => (),
+                    None => {
+                        try!(CapWriter::write_pcap_header(&mut self.w, rec.link_type));
+                        *link_type = Some(rec.link_type);
+                    }
+                }
+                try!(self.w.write_u32::<LittleEndian>(rec.ts_sec as u32));
+                try!(self.w.write_u32::<LittleEndian>(rec.ts_nsec));
+                try!(self.w.write_u32::<LittleEndian>(rec.data.len() as u32));
+                try!(self.w.write_u32::<LittleEndian>(rec.orig_len));
+                try!(self.w.write_all(&rec.data));
+            }
+            WriteFormat::Pcapng { ref mut interfaces } => {
+                let key = (rec.interface, rec.link_type);
+                let if_id = match interfaces.iter().position(|i| *i == key) {
+                    Some(i) => i,
+                    None => {
+                        let mut body: Vec<u8> = Vec::new();
+                        try!(body.write_u16::<LittleEndian>(rec.link_type as u16));
+                        try!(body.write_u16::<LittleEndian>(0)); // reserved
+                        try!(body.write_u32::<LittleEndian>(SNAPLEN));
+                        // nanosecond timestamps, then opt_endofopt
+                        try!(body.write_u16::<LittleEndian>(PCAPNG_OPT_IF_TSRESOL));
+                        try!(body.write_u16::<LittleEndian>(1));
+                        try!(body.write_all(&[9, 0, 0, 0]));
+                        try!(body.write_u32::<LittleEndian>(0));
+                        try!(CapWriter::write_block(&mut self.w, PCAPNG_IDB, &body));
+                        interfaces.push(key);
+                        interfaces.len() - 1
+                    }
+                };
+                let ts = rec.ts_sec * 1000000000 + rec.ts_nsec as u64;
+                let mut body: Vec<u8> = Vec::new();
+                try!(body.write_u32::<LittleEndian>(if_id as u32));
+                try!(body.write_u32::<LittleEndian>((ts >> 32) as u32));
+                try!(body.write_u32::<LittleEndian>(ts as u32));
+                try!(body.write_u32::<LittleEndian>(rec.data.len() as u32));
+                try!(body.write_u32::<LittleEndian>(rec.orig_len));
+                try!(body.write_all(&rec.data));
+                try!(CapWriter::write_block(&mut self.w, PCAPNG_EPB, &body));
+            }
+        }
+        Ok(true)
+    }
+    pub fn flush(&mut self) -> io::Result<()> {
+        // an empty pcap file still needs a header to be readable
+        if let WriteFormat::Pcap { ref mut link_type } = self.format {
+            if link_type.is_none() {
+                try!(CapWriter::write_pcap_header(&mut self.w, LINKTYPE_NETLINK));
+                *link_type = Some(LINKTYPE_NETLINK);
+            }
+        }
+        self.w.flush()
+    }
+    fn write_pcap_header(w: &mut Box<Write>, link_type: u32) -> io::Result<()> {
+        try!(w.write_u32::<LittleEndian>(PCAP_MAGIC_NSEC));
+        try!(w.write_u16::<LittleEndian>(2)); // version_major
+        try!(w.write_u16::<LittleEndian>(4)); // version_minor
+        try!(w.write_i32::<LittleEndian>(0)); // thiszone
+        try!(w.write_u32::<LittleEndian>(0)); // sigfigs
+        try!(w.write_u32::<LittleEndian>(SNAPLEN));
+        try!(w.write_u32::<LittleEndian>(link_type));
+        Ok(())
+    }
+    /// Write a pcapng block, padding the body to 32 bits
+    fn write_block(w: &mut Box<Write>, block_type: u32, body: &[u8]) -> io::Result<()> {
+        let pad = (4 - body.len() % 4) % 4;
+        let total_len = (body.len() + pad + 12) as u32;
+        try!(w.write_u32::<LittleEndian>(block_type));
+        try!(w.write_u32::<LittleEndian>(total_len));
+        try!(w.write_all(body));
+        try!(w.write_all(&[0u8; 3][..pad]));
+        try!(w.write_u32::<LittleEndian>(total_len));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn pcapng_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let len = (body.len() + 12) as u32;
+    let mut v = Vec::new();
+    v.extend_from_slice(&block_type.to_le_bytes());
+    v.extend_from_slice(&len.to_le_bytes());
+    v.extend_from_slice(body);
+    v.extend_from_slice(&len.to_le_bytes());
+    v
+}
+
+#[test]
+fn test_capreader_pcap() {
+    let mut file: Vec<u8> = vec![0xD4, 0xC3, 0xB2, 0xA1, 2, 0, 4, 0, 0, 0, 0, 0,
+                                 0, 0, 0, 0, 0xFF, 0xFF, 0, 0, 113, 0, 0, 0];
+    // ts_sec = 10, ts_usec = 20, incl_len = 2, orig_len = 2
+    file.extend_from_slice(&[10, 0, 0, 0, 20, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 0xAB, 0xCD]);
+    // a second record that got cut off
+    file.extend_from_slice(&[11, 0, 0, 0, 20, 0, 0, 0, 9, 0, 0, 0, 9, 0, 0, 0, 1]);
+    let mut r = CapReader::new(Box::new(Cursor::new(file))).unwrap();
+
+    let rec = r.next().unwrap().unwrap();
+    assert!(rec.link_type == 113);
+    assert!(rec.ts_sec == 10);
+    assert!(rec.ts_nsec == 20000);
+    assert!(rec.data == vec![0xAB, 0xCD]);
+    assert!(r.next().unwrap().is_none());
+}
+
+#[test]
+fn test_capreader_pcapng() {
+    let mut shb = Vec::new();
+    shb.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes());
+    shb.extend_from_slice(&[1, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+    // nlmon0 with nanosecond timestamps, then eth0 with the default
+    let idb0 = [253u8, 0, 0, 0, 0, 0, 4, 0, 9, 0, 1, 0, 9, 0, 0, 0, 0, 0, 0, 0];
+    let idb1 = [1u8, 0, 0, 0, 0, 0, 4, 0];
+    let mut epb0 = vec![0u8, 0, 0, 0, 0, 0, 0, 0, 0x15, 0xCD, 0x5B, 0x07, 2, 0, 0, 0, 2, 0, 0, 0];
+    epb0.extend_from_slice(&[1, 2, 0, 0]);
+    let mut epb1 = vec![1u8, 0, 0, 0, 0, 0, 0, 0, 0x40, 0x42, 0x0F, 0, 3, 0, 0, 0, 60, 0, 0, 0];
+    epb1.extend_from_slice(&[3, 4, 5, 0]);
+
+    let mut file = pcapng_block(PCAPNG_SHB, &shb);
+    file.extend(pcapng_block(PCAPNG_IDB, &idb0));
+    file.extend(pcapng_block(PCAPNG_IDB, &idb1));
+    file.extend(pcapng_block(PCAPNG_EPB, &epb0));
+    // an interface statistics block that should be skipped
+    file.extend(pcapng_block(5, &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+    file.extend(pcapng_block(PCAPNG_EPB, &epb1));
+    let mut r = CapReader::new(Box::new(Cursor::new(file))).unwrap();
+
+    let rec = r.next().unwrap().unwrap();
+    assert!(rec.interface == 0);
+    assert!(rec.link_type == 253);
+    assert!(rec.ts_sec == 0);
+    assert!(rec.ts_nsec == 123456789);
+    assert!(rec.data == vec![1, 2]);
+
+    let rec = r.next().unwrap().unwrap();
+    assert!(rec.interface == 1);
+    assert!(rec.link_type == 1);
+    assert!(rec.ts_sec == 1);
+    assert!(rec.ts_nsec == 0);
+    assert!(rec.orig_len == 60);
+    assert!(rec.data == vec![3, 4, 5]);
+
+    assert!(r.next().unwrap().is_none());
+}
+
+#[test]
+fn test_capreader_pcapng_short_block() {
+    let mut shb = Vec::new();
+    shb.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_le_bytes());
+    shb.extend_from_slice(&[1, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+    let idb = [253u8, 0, 0, 0, 0, 0, 4, 0];
+    let mut epb = vec![0u8, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0];
+    epb.extend_from_slice(&[1, 2, 0, 0]);
+
+    let mut file = pcapng_block(PCAPNG_SHB, &shb);
+    file.extend(pcapng_block(PCAPNG_IDB, &idb));
+    // an EPB with no body at all, then a good one
+    file.extend(pcapng_block(PCAPNG_EPB, &[]));
+    file.extend(pcapng_block(PCAPNG_EPB, &epb));
+    let mut r = CapReader::new(Box::new(Cursor::new(file))).unwrap();
+
+    let e = r.next().unwrap_err();
+    assert!(e.kind() == io::ErrorKind::InvalidData);
+    let rec = r.next().unwrap().unwrap();
+    assert!(rec.ts_sec == 0);
+    assert!(rec.ts_nsec == 5000);
+    assert!(rec.data == vec![1, 2]);
+    assert!(r.next().unwrap().is_none());
+}
+
+#[test]
+fn test_capwriter_round_trip() {
+    let recs = vec![Record { interface: 0, link_type: 113, ts_sec: 1452822917,
+                             ts_nsec: 123456789, orig_len: 3, data: vec![1, 2, 3] },
+                    Record { interface: 1, link_type: 1, ts_sec: 1452822918,
+                             ts_nsec: 5, orig_len: 60, data: vec![4, 5, 6, 7, 8] },
+                    Record { interface: 0, link_type: 113, ts_sec: 1452822919,
+                             ts_nsec: 0, orig_len: 1, data: vec![9] }];
+    let dir = ::std::env::temp_dir();
+    let pid = ::std::process::id();
+
+    for &(ext, skipped) in [("pcap", true), ("pcapng", false)].iter() {
+        let path = dir.join(format!("nl-dump-test-{}.{}", pid, ext));
+        let path = path.to_str().unwrap();
+        let written = CapWriter::create(path).and_then(|mut w| {
+            let mut written = Vec::new();
+            for r in recs.iter() {
+                written.push(try!(w.write(r)));
+            }
+            try!(w.flush());
+            Ok(written)
+        });
+        let read = File::open(path).and_then(|f| {
+            let mut r = try!(CapReader::new(Box::new(f)));
+            let mut read = Vec::new();
+            while let Some(rec) = try!(r.next()) {
+                read.push(rec);
+            }
+            Ok(read)
+        });
+        let _ = ::std::fs::remove_file(path);
+
+        let written = written.unwrap();
+        for (r, w) in recs.iter().zip(written.iter()) {
+            assert!(*w == !(skipped && r.link_type == 1));
+        }
+        let read = read.unwrap();
+        let expected: Vec<&Record> = recs.iter()
+            .filter(|r| !(skipped && r.link_type == 1))
+            .collect();
+        assert!(read.len() == expected.len());
+        for (rec, expected) in read.iter().zip(expected.iter()) {
+            assert!(rec.link_type == expected.link_type);
+            assert!(rec.ts_sec == expected.ts_sec);
+            assert!(rec.ts_nsec == expected.ts_nsec);
+            assert!(rec.orig_len == expected.orig_len);
+            assert!(rec.data == expected.data);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Parsers for netlink (mostly NETLINK_ROUTE) messages, and the capture
+//! plumbing that `nl-dump` is built from.
+//!
+//! ```no_run
+//! extern crate nl_dump;
+//! use nl_dump::nl;
+//!
+//! # fn main() {
+//! # let buf: Vec<u8> = Vec::new();
+//! // buf holds what recv() got from a NETLINK_ROUTE socket
+//! for m in nl::NlMsg::read_raw(&buf, nl::netlink::NetlinkFamily::NETLINK_ROUTE) {
+//!     if let nl::NlMsgEnum::Ifinfomsg(ref link) = m.nlmsg {
+//!         for attr in link.ifi_attr.iter() {
+//!             println!("{}: {} bytes", attr.rta_type, attr.rta_value.len());
+//!         }
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! * `nl` parses buffers into `NlMsg`s, the headers and attributes that they
+//!   are made of, and holds the enums generated from the kernel headers.
+//! * `capfile` reads and writes pcap and pcapng files.
+//! * `textdump` pulls netlink buffers out of strace output and hex dumps.
+//! * `monitor` is a bare netlink socket that listens to multicast groups.
+
+extern crate libc;
+#[macro_use]
+extern crate log;
+extern crate byteorder;
+extern crate num;
+
+pub mod nl;
+pub mod capfile;
+pub mod textdump;
+pub mod monitor;
+mod yaml;
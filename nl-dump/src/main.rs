@@ -4,6 +4,12 @@ use getopts::Options;
 use std::env;
 use std::str::FromStr;
 use std::io::prelude::*;
+use std::io::BufReader;
+use std::fs::File;
+use num::FromPrimitive;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
 
 #[macro_use]
 extern crate log;
@@ -12,20 +18,38 @@ extern crate env_logger; // TODO: replace
 extern crate pcap;
 use pcap::*;
 
-extern crate byteorder;
-
-#[macro_use]
 extern crate num;
 
-#[allow(dead_code)]
-mod nl;
+extern crate nl_dump;
+use nl_dump::{nl, capfile, textdump, monitor};
 
 #[derive(Debug)]
 #[derive(Default)]
 struct Args {
     input: Option<String>,
+    interface: Option<String>,
+    monitor: Option<Vec<nl::rtnetlink::RtnlGrp>>,
+    strace: Option<String>,
+    hex: Option<String>,
+    count: Option<usize>,
+    netlink_only: bool,
     netlink_family: Option<nl::netlink::NetlinkFamily>,
+    nlmsg_type: Option<nl::NlMsgTypeEnum>,
+    ifindex: Option<i32>,
+    write: Option<String>,
+    specs: Option<nl::spec::SpecSet>,
+    stats_delta: bool,
 }
+impl Args {
+    /// The family to decode as when the input doesn't tell us
+    fn default_family(&self) -> nl::netlink::NetlinkFamily {
+        self.netlink_family.unwrap_or(nl::netlink::NetlinkFamily::NETLINK_ROUTE)
+    }
+}
+
+/// For --stats-delta, the packet that we last saw counters for each ifindex
+/// in, what they were and how many bits wide
+type LastStats = HashMap<i32, (usize, Vec<(&'static str, u64)>, u32)>;
 
 fn parse_options() -> Args {
     let args: Vec<String> = env::args().collect();
@@ -33,9 +57,38 @@ fn parse_options() -> Args {
     let mut a = Args::default();
 
     let mut opts = Options::new();
-    opts.optopt("i", "input", "pcap input file", "NAME");
+    opts.optopt("i", "input", "pcap input file, - for standard in (the \
+                default)", "NAME");
+    opts.optopt("", "interface", "capture live from an interface (nlmon0, \
+                etc)", "NAME");
+    opts.optopt("", "monitor", "listen to NETLINK_ROUTE multicast groups \
+                directly, no pcap required (link,ipv4_ifaddr,ipv6_ifaddr,\
+                ipv4_route,ipv6_route,neigh,tc, etc)", "GROUPS");
+    opts.optopt("", "strace", "decode the netlink buffers in strace output \
+                (strace -xx -s 65536 -e trace=network), - for standard in",
+                "NAME");
+    opts.optopt("", "hex", "decode hex dumps of netlink buffers, one per \
+                paragraph, - for standard in", "NAME");
+    opts.optopt("c", "count", "exit after this many packets", "NUM");
     opts.optopt("", "netlink_family", "filter for one netlink_family (\
-                NETLINK_ROUTE, NETLINK_GENERIC, etc)", "FAMILY");
+                NETLINK_ROUTE, NETLINK_GENERIC, etc). Input without a cooked \
+                header is decoded as this family, NETLINK_ROUTE by default",
+                "FAMILY");
+    opts.optopt("", "nlmsg_type", "filter for one nlmsg_type (RTM_NEWLINK, \
+                NLMSG_ERROR, 26, etc)", "TYPE");
+    opts.optopt("", "ifindex", "filter for messages about one interface \
+                index", "INDEX");
+    opts.optopt("w", "write", "write the packets that match the filters to \
+                a file instead of printing them. pcapng if NAME ends with \
+                .pcapng, otherwise pcap", "NAME");
+    opts.optopt("", "spec-dir", "decode the families that the netlink specs \
+                (*.yaml) in DIR describe with them, eg. the kernel's \
+                Documentation/netlink/specs", "DIR");
+    opts.optflag("", "stats-delta", "after each RTM_NEWLINK with counters, \
+                 print how much they went up since the last one for that \
+                 interface");
+    opts.optflag("", "netlink-only", "skip packets from interfaces that \
+                 aren't netlink instead of summarizing them");
     opts.optflag("h", "help", "print this help menu");
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => { m }
@@ -46,17 +99,85 @@ fn parse_options() -> Args {
         std::process::exit(0);
     }
     a.input = matches.opt_str("i");
-    if a.input.is_none() {
-        error!("we need an input file");
-        print_usage(&program, opts);
-        std::process::exit(0);
+    a.netlink_only = matches.opt_present("netlink-only");
+    a.stats_delta = matches.opt_present("stats-delta");
+    a.interface = matches.opt_str("interface");
+    a.monitor = match matches.opt_str("monitor") {
+        Some(s) => match monitor::parse_groups(&*s) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                error!("{}", e);
+                print_usage(&program, opts);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    a.strace = matches.opt_str("strace");
+    a.hex = matches.opt_str("hex");
+    let sources = [a.input.is_some(), a.interface.is_some(), a.monitor.is_some(),
+                   a.strace.is_some(), a.hex.is_some()];
+    match sources.iter().filter(|s| **s).count() {
+        // read a pcap stream from standard in, eg. tcpdump -w - | nl-dump
+        0 => a.input = Some(String::from("-")),
+        1 => (),
+        _ => {
+            error!("--input, --interface, --monitor, --strace and --hex are \
+                    mutually exclusive");
+            print_usage(&program, opts);
+            std::process::exit(1);
+        }
     }
+    a.count = match matches.opt_str("c") {
+        Some(s) => match usize::from_str(&*s) {
+            Ok(c) if c > 0 => Some(c),
+            _ => {
+                error!("count must be a positive number, not {}", s);
+                print_usage(&program, opts);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
     let netlink_family = matches.opt_str("netlink_family");
     a.netlink_family = match netlink_family {
         // This is confusing. &*s = explicitly reborrowing String as &str.
         Some(s) => Some(nl::netlink::NetlinkFamily::from_str(&*s).unwrap()),
         None => None,
     };
+    a.nlmsg_type = match matches.opt_str("nlmsg_type") {
+        Some(s) => match nl::NlMsgTypeEnum::from_str(&*s) {
+            Ok(t) => Some(t),
+            Err(_) => {
+                error!("unknown nlmsg_type {}", s);
+                print_usage(&program, opts);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    a.ifindex = match matches.opt_str("ifindex") {
+        Some(s) => match i32::from_str(&*s) {
+            Ok(i) => Some(i),
+            Err(_) => {
+                error!("ifindex must be a number, not {}", s);
+                print_usage(&program, opts);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    a.write = matches.opt_str("w");
+    a.specs = match matches.opt_str("spec-dir") {
+        Some(s) => match nl::spec::SpecSet::load_dir(std::path::Path::new(&s)) {
+            Ok(specs) => Some(specs),
+            Err(e) => {
+                error!("couldn't read the specs in {}: {}", s, e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
     a
 }
 
@@ -65,44 +186,453 @@ fn print_usage(program: &str, opts: Options) {
     print!("{}", opts.usage(&brief));
 }
 
-fn print_packets(args: &Args) {
-    debug!("print_packets({:?})", args);
-    // open a new capture from the test.pcap file we wrote to above
-    // let path = args.input.unwrap();
-	let mut cap = match args.input {
-        Some(ref s) => Capture::from_file(s).unwrap(),
-        None => panic!(),
+// Set from the SIGINT handler so that a live capture can stop cleanly
+static STOP: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn handle_sigint(_: libc::c_int) {
+    STOP.store(true, Ordering::SeqCst);
+}
+
+fn install_sigint_handler() {
+    unsafe {
+        libc::signal(libc::SIGINT, handle_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+}
+
+/// Have we printed as many packets as we were asked to, or been told to stop?
+fn done(args: &Args, p: usize) -> bool {
+    if STOP.load(Ordering::SeqCst) {
+        return true;
+    }
+    match args.count {
+        Some(c) => p >= c,
+        None => false,
+    }
+}
+
+fn open_input(path: &str) -> capfile::CapReader {
+    let r: Box<Read> = if path == "-" {
+        Box::new(std::io::stdin())
+    }
+    else {
+        match File::open(path) {
+            Ok(f) => Box::new(BufReader::new(f)),
+            Err(e) => {
+                error!("couldn't open {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    };
+    match capfile::CapReader::new(r) {
+        Ok(c) => c,
+        Err(e) => {
+            error!("couldn't read {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn open_text(path: &str, format: textdump::TextFormat) -> textdump::TextReader {
+    let r: Box<BufRead> = if path == "-" {
+        Box::new(BufReader::new(std::io::stdin()))
+    }
+    else {
+        match File::open(path) {
+            Ok(f) => Box::new(BufReader::new(f)),
+            Err(e) => {
+                error!("couldn't open {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    };
+    textdump::TextReader::new(r, format)
+}
+
+fn open_interface(interface: &str) -> Capture<Active> {
+    // The timeout keeps next() from blocking forever so that we notice
+    // Ctrl-C even when the interface is quiet.
+    let cap = Capture::from_device(interface)
+        .and_then(|c| c.promisc(false).timeout(100).open());
+    match cap {
+        Ok(c) => c,
+        Err(e) => {
+            error!("couldn't open interface {}: {}", interface, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn open_output(path: &str) -> capfile::CapWriter {
+    match capfile::CapWriter::create(path) {
+        Ok(w) => w,
+        Err(e) => {
+            error!("couldn't create {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Are there any filters that only make sense for netlink messages?
+fn has_msg_filters(args: &Args) -> bool {
+    args.netlink_family.is_some() || args.nlmsg_type.is_some() || args.ifindex.is_some()
+}
+
+/// Does this message get through --netlink_family, --nlmsg_type and --ifindex?
+fn msg_matches(args: &Args, m: &nl::NlMsg) -> bool {
+    if let Some(f) = args.netlink_family {
+        if m.netlink_family != nl::Value::Known(f) {
+            return false;
+        }
+    }
+    if let Some(t) = args.nlmsg_type {
+        let matched = match t {
+            // a number matches whatever it was decoded as
+            nl::NlMsgTypeEnum::Raw(u) => u == m.nlmsghdr.nlmsg_type.value(),
+            _ => t == m.nlmsghdr.nlmsg_type,
+        };
+        if !matched {
+            return false;
+        }
+    }
+    if let Some(i) = args.ifindex {
+        if m.nlmsg.ifindex() != Some(i) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Wrap a bare netlink buffer up like libpcap does on an nlmon interface, with
+/// a cooked header, so that the family survives being written out.
+fn netlink_record(family: nl::netlink::NetlinkFamily, ts: (u64, u32),
+                  payload: &[u8]) -> capfile::Record {
+    let mut data = vec![0, 4, 3, 56, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                        (family as u16 >> 8) as u8, family as u8];
+    data.extend_from_slice(payload);
+    capfile::Record { interface: 0,
+                      link_type: nl::netlink::LinkType::LINKTYPE_NETLINK as u32,
+                      ts_sec: ts.0,
+                      ts_nsec: ts.1,
+                      orig_len: data.len() as u32,
+                      data: data }
+}
+
+fn print_packet(args: &Args, p: usize, desc: &str, vec: &Vec<nl::NlMsg>,
+                last_stats: &mut LastStats) {
+    let mut first = true;
+    for m in vec.iter() {
+        // Skip the messages that we aren't looking for
+        if !msg_matches(args, m) {
+            continue;
+        }
+        if first {
+            print!("packet[{}] ({}) = [ ", p, desc);
+            first = false;
+        }
+        else {
+            print!(",\n    ");
+        }
+
+        {
+            let i = nl::Indent { t: m, i: 1 };
+            print!("{}", i);
+        }
+    }
+    if !first {
+        println!("\n]");
+    }
+    if args.stats_delta {
+        for m in vec.iter().filter(|m| msg_matches(args, m)) {
+            print_stats_delta(p, m, last_stats);
+        }
+    }
+}
+
+/// For --stats-delta, print which counters of an RTM_NEWLINK changed since
+/// the last one for the same interface
+fn print_stats_delta(p: usize, m: &nl::NlMsg, last_stats: &mut LastStats) {
+    if m.nlmsghdr.nlmsg_type != nl::NlMsgTypeEnum::NrMsgType(nl::rtnetlink::NrMsgType::RTM_NEWLINK) {
+        return;
+    }
+    let link = match m.nlmsg {
+        nl::NlMsgEnum::Ifinfomsg(ref link) => link,
+        _ => return,
     };
+    let (stats, bits) = match link.stats() {
+        Some(s) => s,
+        None => return,
+    };
+    if let Some(&(last_p, ref last, last_bits)) = last_stats.get(&link.ifi_index) {
+        let mut changed = Vec::new();
+        for &(name, n) in stats.iter() {
+            // IFLA_STATS64 and IFLA_STATS don't have quite the same counters
+            if let Some(&(_, last_n)) = last.iter().find(|&&(l, _)| l == name) {
+                if n != last_n {
+                    match counter_delta(last_n, n, bits.min(last_bits)) {
+                        Some(delta) => changed.push(format!("{} +{}", name, delta)),
+                        None => changed.push(format!("{} reset to {}", name, n)),
+                    }
+                }
+            }
+        }
+        if changed.is_empty() {
+            println!("packet[{}] ifindex {} counters since packet[{}]: no change",
+                     p, link.ifi_index, last_p);
+        }
+        else {
+            println!("packet[{}] ifindex {} counters since packet[{}]: {}",
+                     p, link.ifi_index, last_p, changed.join(", "));
+        }
+    }
+    last_stats.insert(link.ifi_index, (p, stats, bits));
+}
 
-    let mut p: i32 = 0;
-    while let Ok(packet) = cap.next() {
-        p = p + 1;
-        let vec = nl::NlMsg::read(packet.data);
-
-        let mut first = true;
-        for m in vec.iter() {
-            // Skip these messages if this isn't the family that we are looking for
-            match args.netlink_family {
-                Some(ref f) => if *f != m.netlink_family { continue },
-                None => (),
+/// How much a counter that is `bits` wide went up from `last` to `n`, or None
+/// if it was reset. A counter that wrapped went up by a little, so going down
+/// is only a wrap if it went up by less than half of what the counter holds.
+fn counter_delta(last: u64, n: u64, bits: u32) -> Option<u64> {
+    if n >= last {
+        return Some(n - last);
+    }
+    let mask = if bits < 64 { (1 << bits) - 1 } else { u64::max_value() };
+    let wrapped = n.wrapping_sub(last) & mask;
+    if wrapped <= mask / 2 {
+        Some(wrapped)
+    }
+    else {
+        None
+    }
+}
+
+/// One line for packets that aren't netlink, so that they can be lined up
+/// with the netlink traffic around them.
+fn print_summary(p: usize, rec: &capfile::Record) {
+    print!("packet[{}] ({}.{:06}) interface {}: ", p, rec.ts_sec, rec.ts_nsec / 1000,
+           rec.interface);
+    match nl::netlink::LinkType::from_u32(rec.link_type) {
+        Some(l) => print!("{}", l),
+        None => print!("LINKTYPE {}", rec.link_type),
+    }
+    print!(", {} bytes", rec.orig_len);
+    if nl::netlink::LinkType::from_u32(rec.link_type) == Some(nl::netlink::LinkType::LINKTYPE_ETHERNET) &&
+       rec.data.len() >= 14 {
+        let d = &rec.data;
+        print!(", {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x} > \
+                {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}, ethertype {:#06x}",
+               d[6], d[7], d[8], d[9], d[10], d[11],
+               d[0], d[1], d[2], d[3], d[4], d[5],
+               ((d[12] as u16) << 8) | d[13] as u16);
+    }
+    println!("");
+}
+
+/// Print a packet, or write it out if it matches the filters and we have
+/// somewhere to write it. The label is printed instead of the timestamp.
+fn handle_record(args: &Args, p: usize, rec: &capfile::Record, label: Option<&str>,
+                 out: &mut Option<capfile::CapWriter>, last_stats: &mut LastStats) {
+    let link_type = nl::netlink::LinkType::from_u32(rec.link_type);
+    // non-netlink packets can't get through the netlink filters
+    let show_other = !args.netlink_only && !has_msg_filters(args);
+    let vec = match link_type {
+        Some(nl::netlink::LinkType::LINKTYPE_LINUX_SLL) |
+        Some(nl::netlink::LinkType::LINKTYPE_LINUX_SLL2) |
+        Some(nl::netlink::LinkType::LINKTYPE_NETLINK) => {
+            Some(nl::NlMsg::read_link_type_with(&rec.data, link_type.unwrap(),
+                                                args.default_family(), args.specs.as_ref()))
+        }
+        _ => None,
+    };
+
+    match *out {
+        Some(ref mut w) => {
+            let matched = match vec {
+                Some(Ok(ref v)) => v.iter().any(|m| msg_matches(args, m)),
+                // there are no messages for the filters to look at
+                Some(Err(_)) | None => show_other,
             };
-            if first {
-                print!("packet[{}] = [ ", p);
-                first = false;
+            if matched {
+                match w.write(rec) {
+                    Ok(true) => (),
+                    Ok(false) => info!("packet[{}] skipped, the output can't hold \
+                                        link type {}", p, rec.link_type),
+                    Err(e) => {
+                        error!("couldn't write packet[{}]: {}", p, e);
+                        std::process::exit(1);
+                    }
+                }
             }
-            else {
-                print!(",\n    ");
+        }
+        None => {
+            let desc = match label {
+                Some(l) => String::from(l),
+                None => format!("{}.{:06}", rec.ts_sec, rec.ts_nsec / 1000),
+            };
+            match vec {
+                Some(Ok(ref v)) => print_packet(args, p, &desc, v, last_stats),
+                Some(Err(ref e)) => {
+                    if show_other {
+                        println!("packet[{}] ({}) couldn't be decoded: {}", p, desc, e);
+                    }
+                }
+                None => {
+                    if show_other {
+                        print_summary(p, rec);
+                    }
+                }
             }
+            // streaming output is useless if it sits in a buffer
+            let _ = std::io::stdout().flush();
+        }
+    }
+}
+
+fn close_output(out: Option<capfile::CapWriter>) {
+    if let Some(mut w) = out {
+        if let Err(e) = w.flush() {
+            error!("couldn't write the output: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn capture_live(args: &Args, interface: &str) {
+    debug!("capture_live({:?})", args);
+    let mut cap = open_interface(interface);
+    let link_type = cap.get_datalink().0 as u32;
+    let mut out = args.write.as_ref().map(|w| open_output(w));
+    install_sigint_handler();
 
-            {
-                let i = nl::Indent { t: m, i: 1 };
-                print!("{}", i);
+    let mut last_stats = LastStats::new();
+    let mut p: usize = 0;
+    while !done(args, p) {
+        match cap.next() {
+            Ok(packet) => {
+                p = p + 1;
+                let rec = capfile::Record { interface: 0,
+                                            link_type: link_type,
+                                            ts_sec: packet.header.ts.tv_sec as u64,
+                                            ts_nsec: packet.header.ts.tv_usec as u32 * 1000,
+                                            orig_len: packet.header.len,
+                                            data: packet.data.to_vec() };
+                handle_record(args, p, &rec, None, &mut out, &mut last_stats);
+            }
+            Err(Error::TimeoutExpired) => continue,
+            Err(e) => {
+                error!("capture on {} failed: {}", interface, e);
+                break;
+            }
+        }
+    }
+    close_output(out);
+}
+
+fn print_packets(args: &Args) {
+    debug!("print_packets({:?})", args);
+    let path = args.input.as_ref().unwrap();
+    let mut input = open_input(path);
+    let mut out = args.write.as_ref().map(|w| open_output(w));
+    install_sigint_handler();
+
+    let mut last_stats = LastStats::new();
+    let mut p: usize = 0;
+    while !done(args, p) {
+        match input.next() {
+            Ok(Some(rec)) => {
+                p = p + 1;
+                handle_record(args, p, &rec, None, &mut out, &mut last_stats);
+            }
+            Ok(None) => break,
+            Err(e) => {
+                error!("stopped reading {} after packet {}: {}", path, p, e);
+                break;
+            }
+        }
+    }
+    close_output(out);
+}
+
+fn monitor_groups(args: &Args, groups: &Vec<nl::rtnetlink::RtnlGrp>) {
+    debug!("monitor_groups({:?})", args);
+    let family = nl::netlink::NetlinkFamily::NETLINK_ROUTE;
+    let sock = match monitor::NlSocket::open(family, 100) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("couldn't open a netlink socket: {}", e);
+            std::process::exit(1);
+        }
+    };
+    for g in groups.iter() {
+        if let Err(e) = sock.join(*g as u32) {
+            error!("couldn't join {}: {}", g, e);
+            std::process::exit(1);
+        }
+    }
+
+    let mut out = args.write.as_ref().map(|w| open_output(w));
+    install_sigint_handler();
+
+    // big enough for anything the kernel will send us in one go
+    let mut buf = vec![0u8; 65536];
+    let mut last_stats = LastStats::new();
+    let mut p: usize = 0;
+    while !done(args, p) {
+        match sock.recv(&mut buf) {
+            Ok(Some(len)) => {
+                p = p + 1;
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+                let rec = netlink_record(family, (now.as_secs(), now.subsec_nanos()), &buf[..len]);
+                handle_record(args, p, &rec, None, &mut out, &mut last_stats);
+            }
+            Ok(None) => continue,
+            Err(ref e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                // the kernel dropped messages because we were too slow
+                error!("netlink socket overrun, some messages were lost");
+            }
+            Err(e) => {
+                error!("recv() failed: {}", e);
+                break;
             }
         }
-        if !first {
-            println!("\n]");
+    }
+    close_output(out);
+}
+
+fn print_text(args: &Args, path: &str, format: textdump::TextFormat) {
+    debug!("print_text({:?})", args);
+    let mut input = open_text(path, format);
+    let mut out = args.write.as_ref().map(|w| open_output(w));
+    install_sigint_handler();
+
+    let mut last_stats = LastStats::new();
+    let mut p: usize = 0;
+    while !done(args, p) {
+        match input.next() {
+            Ok(Some(b)) => {
+                p = p + 1;
+                if b.truncated {
+                    warn!("packet[{}] from line {} was cut off, try a bigger strace -s",
+                          p, b.line);
+                }
+                // strace doesn't know the family, so the user has to
+                let rec = netlink_record(args.default_family(), b.epoch().unwrap_or((0, 0)),
+                                         &b.data);
+                let mut label = format!("line {}", b.line);
+                if let Some(ref ts) = b.timestamp {
+                    label = format!("{} {}", label, ts);
+                }
+                if let (Some(ref syscall), Some(dir)) = (b.syscall.as_ref(), b.direction) {
+                    label = format!("{} {} {}", label, syscall, dir);
+                }
+                handle_record(args, p, &rec, Some(&label), &mut out, &mut last_stats);
+            }
+            Ok(None) => break,
+            Err(e) => {
+                error!("stopped reading {} after packet {}: {}", path, p, e);
+                break;
+            }
         }
     }
+    close_output(out);
 }
 
 fn main() {
@@ -110,5 +640,30 @@ fn main() {
     debug!("main()");
     let args: Args = parse_options();
 
-    print_packets(&args);
+    if let Some(ref groups) = args.monitor {
+        monitor_groups(&args, groups);
+    }
+    else if let Some(ref interface) = args.interface {
+        capture_live(&args, interface);
+    }
+    else if let Some(ref path) = args.strace {
+        print_text(&args, path, textdump::TextFormat::Strace);
+    }
+    else if let Some(ref path) = args.hex {
+        print_text(&args, path, textdump::TextFormat::Hex);
+    }
+    else {
+        print_packets(&args);
+    }
+}
+
+#[test]
+fn test_counter_delta() {
+    assert!(counter_delta(10, 25, 32) == Some(15));
+    // counters that wrapped
+    assert!(counter_delta(0xFFFFFFF0, 0x10, 32) == Some(0x20));
+    assert!(counter_delta(0xFFFFFFFFFFFFFFF0, 0x10, 64) == Some(0x20));
+    // and ones that were reset, whatever their width
+    assert!(counter_delta(25, 10, 32) == None);
+    assert!(counter_delta(25, 10, 64) == None);
 }
@@ -0,0 +1,130 @@
+/* A bare AF_NETLINK socket that listens to multicast groups. This lets us see
+ * what is going on without pcap, the nlmon module or root (a user namespace is
+ * enough).
+ */
+
+use ::std::io;
+use ::std::mem;
+use ::std::str::FromStr;
+use ::libc;
+use nl::netlink::NetlinkFamily;
+use nl::rtnetlink::RtnlGrp;
+
+// from linux/netlink.h, declared here because not every libc has them
+const SOL_NETLINK: libc::c_int = 270;
+const NETLINK_ADD_MEMBERSHIP: libc::c_int = 1;
+
+#[repr(C)]
+struct SockaddrNl {
+    nl_family: libc::sa_family_t,
+    nl_pad: libc::c_ushort,
+    nl_pid: u32,
+    nl_groups: u32,
+}
+
+pub struct NlSocket {
+    fd: libc::c_int,
+}
+impl NlSocket {
+    /// Open and bind a netlink socket for `family`. recv() will give up after
+    /// `timeout_ms` so that the caller gets a chance to look around.
+    pub fn open(family: NetlinkFamily, timeout_ms: i64) -> io::Result<NlSocket> {
+        let fd = unsafe {
+            libc::socket(libc::AF_NETLINK, libc::SOCK_RAW, family as libc::c_int)
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let s = NlSocket { fd: fd };
+
+        let mut addr: SockaddrNl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        let r = unsafe {
+            libc::bind(s.fd, &addr as *const SockaddrNl as *const libc::sockaddr,
+                       mem::size_of::<SockaddrNl>() as libc::socklen_t)
+        };
+        if r < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let tv = libc::timeval { tv_sec: (timeout_ms / 1000) as libc::time_t,
+                                 tv_usec: ((timeout_ms % 1000) * 1000) as libc::suseconds_t };
+        try!(s.setsockopt(libc::SOL_SOCKET, libc::SO_RCVTIMEO, &tv));
+
+        Ok(s)
+    }
+    /// Join a multicast group, eg. RTNLGRP_LINK for NETLINK_ROUTE.
+    pub fn join(&self, group: u32) -> io::Result<()> {
+        self.setsockopt(SOL_NETLINK, NETLINK_ADD_MEMBERSHIP, &group)
+    }
+    /// Receive one buffer worth of netlink messages. Returns Ok(None) if the
+    /// timeout expired before anything showed up.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<Option<usize>> {
+        let r = unsafe {
+            libc::recv(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0)
+        };
+        if r < 0 {
+            let e = io::Error::last_os_error();
+            return match e.kind() {
+                io::ErrorKind::WouldBlock |
+                io::ErrorKind::TimedOut |
+                io::ErrorKind::Interrupted => Ok(None),
+                _ => Err(e),
+            };
+        }
+        Ok(Some(r as usize))
+    }
+    fn setsockopt<T>(&self, level: libc::c_int, name: libc::c_int, value: &T) -> io::Result<()> {
+        let r = unsafe {
+            libc::setsockopt(self.fd, level, name, value as *const T as *const libc::c_void,
+                             mem::size_of::<T>() as libc::socklen_t)
+        };
+        if r < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+impl Drop for NlSocket {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// Parse a comma separated list of groups. The RTNLGRP_ prefix is optional
+/// and case doesn't matter, so "link,ipv4_ifaddr" works.
+pub fn parse_groups(s: &str) -> Result<Vec<RtnlGrp>, String> {
+    let mut vec = Vec::new();
+    for g in s.split(',') {
+        let mut name = g.trim().to_uppercase();
+        if name.is_empty() {
+            continue;
+        }
+        if !name.starts_with("RTNLGRP_") {
+            name = format!("RTNLGRP_{}", name);
+        }
+        // DECnet is the one group that isn't all upper case
+        let name = name.replace("DECNET", "DECnet");
+        match RtnlGrp::from_str(&*name) {
+            Ok(grp) => vec.push(grp),
+            Err(_) => return Err(format!("unknown multicast group {}", g)),
+        }
+    }
+    if vec.is_empty() {
+        return Err(String::from("no multicast groups given"));
+    }
+    Ok(vec)
+}
+
+#[test]
+fn test_parse_groups() {
+    let v = parse_groups("link,ipv4_ifaddr, RTNLGRP_IPV6_ROUTE,neigh,tc,decnet_route").unwrap();
+    assert!(v == vec![RtnlGrp::RTNLGRP_LINK, RtnlGrp::RTNLGRP_IPV4_IFADDR,
+                      RtnlGrp::RTNLGRP_IPV6_ROUTE, RtnlGrp::RTNLGRP_NEIGH,
+                      RtnlGrp::RTNLGRP_TC, RtnlGrp::RTNLGRP_DECnet_ROUTE]);
+
+    assert!(parse_groups("link,bogus").is_err());
+    assert!(parse_groups("").is_err());
+}
@@ -0,0 +1,46 @@
+use ::std;
+use ::std::fmt;
+
+/// What went wrong while decoding a packet
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    /// The packet (or the message or attribute around it) ended in the
+    /// middle of this
+    Truncated(&'static str),
+    /// nlmsg_len is smaller than a nlmsghdr or runs past the end of the packet
+    BadNlmsgLen { nlmsg_len: u32, available: usize },
+    /// rta_len is smaller than an rtattr or runs past the end of the message
+    BadRtaLen { rta_len: u16, available: usize },
+}
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::Truncated(what) => write!(f, "truncated {}", what),
+            ErrorKind::BadNlmsgLen { nlmsg_len, available } =>
+                write!(f, "nlmsg_len of {} doesn't fit in the {} bytes left", nlmsg_len, available),
+            ErrorKind::BadRtaLen { rta_len, available } =>
+                write!(f, "rta_len of {} doesn't fit in the {} bytes left", rta_len, available),
+        }
+    }
+}
+
+/// A decoding error and where in the packet it happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// Bytes from the start of the packet, including any pseudo-header
+    pub offset: usize,
+}
+impl Error {
+    pub fn new(kind: ErrorKind, offset: usize) -> Error {
+        Error { kind: kind, offset: offset }
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.offset)
+    }
+}
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
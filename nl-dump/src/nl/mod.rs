@@ -1,9 +1,12 @@
-#[allow(dead_code)]
 #[allow(non_camel_case_types)]
 pub mod netlink;
-#[allow(dead_code)]
 #[allow(non_camel_case_types)]
 pub mod rtnetlink;
+pub mod error;
+pub mod spec;
+
+// not Result, that would clash with the FromStr impls that build.rs generates
+pub use self::error::{Error, ErrorKind};
 
 /* TODO:
  - better support for nlmsg_flags (NLM_F_ROOT, NLM_F_MATCH, NLM_F_ATOMIC or
@@ -13,16 +16,17 @@ pub mod rtnetlink;
 
 use ::std;
 use ::std::fmt;
-use ::std::io;
 use ::std::io::prelude::*;
 use ::std::io::{Cursor, SeekFrom};
-use ::byteorder::{BigEndian, NativeEndian, ReadBytesExt};
+use ::byteorder::{BigEndian, ByteOrder, NativeEndian, ReadBytesExt};
 use ::num::FromPrimitive;
 
 // this is where the NlMsgFlags enum was generated by build.rs
 include!(concat!(env!("OUT_DIR"), "/nl_msg_flags.rs"));
 
-trait Pretty {
+/// Anything that can be printed the way nl-dump prints it. Use Indent to
+/// print one with Display.
+pub trait Pretty {
     fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result;
 }
 
@@ -34,6 +38,111 @@ fn get_size(cursor: &mut std::io::Cursor<&[u8]>) -> u64 {
     end.unwrap()
 }
 
+/// Make sure that `len` bytes of `what` fit before `end`, so that we can say
+/// exactly where a packet was cut off.
+fn check_len(cursor: &Cursor<&[u8]>, end: usize, len: usize,
+             what: &'static str) -> error::Result<()> {
+    let pos = cursor.position() as usize;
+    if pos + len > end || pos + len > cursor.get_ref().len() {
+        return Err(Error::new(ErrorKind::Truncated(what), pos));
+    }
+    Ok(())
+}
+
+// Reads that stop at `end` (the end of the message or attribute that we are
+// in) and report where they ran out instead of a bare UnexpectedEOF.
+fn read_u8(cursor: &mut Cursor<&[u8]>, end: usize, what: &'static str) -> error::Result<u8> {
+    try!(check_len(cursor, end, 1, what));
+    Ok(cursor.read_u8().unwrap())
+}
+fn read_u16<T: ByteOrder>(cursor: &mut Cursor<&[u8]>, end: usize,
+                          what: &'static str) -> error::Result<u16> {
+    try!(check_len(cursor, end, 2, what));
+    Ok(cursor.read_u16::<T>().unwrap())
+}
+fn read_u32<T: ByteOrder>(cursor: &mut Cursor<&[u8]>, end: usize,
+                          what: &'static str) -> error::Result<u32> {
+    try!(check_len(cursor, end, 4, what));
+    Ok(cursor.read_u32::<T>().unwrap())
+}
+fn read_u64<T: ByteOrder>(cursor: &mut Cursor<&[u8]>, end: usize,
+                          what: &'static str) -> error::Result<u64> {
+    try!(check_len(cursor, end, 8, what));
+    Ok(cursor.read_u64::<T>().unwrap())
+}
+fn read_i32<T: ByteOrder>(cursor: &mut Cursor<&[u8]>, end: usize,
+                          what: &'static str) -> error::Result<i32> {
+    try!(check_len(cursor, end, 4, what));
+    Ok(cursor.read_i32::<T>().unwrap())
+}
+fn read_bytes(cursor: &mut Cursor<&[u8]>, end: usize, len: usize,
+              what: &'static str) -> error::Result<Vec<u8>> {
+    try!(check_len(cursor, end, len, what));
+    let pos = cursor.position() as usize;
+    cursor.set_position((pos + len) as u64);
+    Ok(cursor.get_ref()[pos..pos + len].to_vec())
+}
+
+/// A number from a packet that we may or may not have a name for. The
+/// kernel keeps adding attributes, protocols and so on, so not knowing one
+/// isn't an error. We just show the number.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Value<T> {
+    Known(T),
+    Unknown(u32),
+}
+impl<T: FromPrimitive> Value<T> {
+    pub fn new(n: u32) -> Value<T> {
+        match T::from_u32(n) {
+            Some(t) => Value::Known(t),
+            None => Value::Unknown(n),
+        }
+    }
+}
+impl<T> Value<T> {
+    /// The name, if we have one
+    pub fn known(&self) -> Option<&T> {
+        match *self {
+            Value::Known(ref t) => Some(t),
+            Value::Unknown(_) => None,
+        }
+    }
+}
+impl<T: fmt::Display> fmt::Display for Value<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Known(ref t) => write!(f, "{}", t),
+            Value::Unknown(n) => write!(f, "Unknown({})", n),
+        }
+    }
+}
+impl<T: Default> Default for Value<T> {
+    fn default() -> Value<T> {
+        Value::Known(T::default())
+    }
+}
+
+/// Print the names of the flags that are set, separated by |. Bits that we
+/// don't have a name for are printed in hex.
+fn fmt_flags<T: FromPrimitive + fmt::Display>(f: &mut fmt::Formatter, flags: u32) -> fmt::Result {
+    let mut found = false;
+    for shift in 0..32 {
+        let bit = flags & (1 << shift);
+        if bit == 0 {
+            continue;
+        }
+        if found {
+            try!(write!(f, "|"));
+        }
+        match T::from_u32(bit) {
+            Some(flag) => try!(write!(f, "{}", flag)),
+            None => try!(write!(f, "{:#X}", bit)),
+        }
+        found = true;
+    }
+    Ok(())
+}
+
 fn format_indent(indent: i32) -> String {
     let mut s = String::new();
     for _ in 0..indent {
@@ -46,11 +155,11 @@ fn format_indent(indent: i32) -> String {
 /// http://www.tcpdump.org/linktypes/LINKTYPE_LINUX_SLL.html
 #[derive(Debug)]
 pub struct CookedHeader {
-    header_type: u16,
-    arphdr_type: u16,
-    address_length: u16,
-    address: [u8; 8],
-    netlink_family: netlink::NetlinkFamily, // NETLINK_ROUTE .. NETLINK_INET_DIAG
+    pub header_type: u16,
+    pub arphdr_type: u16,
+    pub address_length: u16,
+    pub address: [u8; 8],
+    pub netlink_family: Value<netlink::NetlinkFamily>, // NETLINK_ROUTE .. NETLINK_INET_DIAG
 }
 pub const COOKED_HEADER_SIZE: usize = 16;
 impl Default for CookedHeader {
@@ -59,7 +168,7 @@ impl Default for CookedHeader {
                       arphdr_type: 0,
                       address_length: 0,
                       address: [0; 8],
-                      netlink_family: netlink::NetlinkFamily::NETLINK_ROUTE }
+                      netlink_family: Value::Known(netlink::NetlinkFamily::NETLINK_ROUTE) }
     }
 }
 impl fmt::Display for CookedHeader {
@@ -69,28 +178,31 @@ impl fmt::Display for CookedHeader {
 }
 impl CookedHeader {
     // Netlink header is native endian
-    pub fn read(cursor: &mut std::io::Cursor<&[u8]>) -> io::Result<CookedHeader> {
+    pub fn read(cursor: &mut std::io::Cursor<&[u8]>) -> error::Result<CookedHeader> {
         debug!("CookedHeader::read()");
         let mut c = CookedHeader::default();
+        let start = cursor.position();
+        let end = start as usize + COOKED_HEADER_SIZE;
+        try!(check_len(cursor, end, COOKED_HEADER_SIZE, "cooked header"));
 
-        c.header_type = try!(cursor.read_u16::<BigEndian>());
-        c.arphdr_type = try!(cursor.read_u16::<BigEndian>());
-        c.address_length = try!(cursor.read_u16::<BigEndian>());
+        c.header_type = try!(read_u16::<BigEndian>(cursor, end, "cooked header"));
+        c.arphdr_type = try!(read_u16::<BigEndian>(cursor, end, "cooked header"));
+        c.address_length = try!(read_u16::<BigEndian>(cursor, end, "cooked header"));
         for a in c.address.iter_mut() {
-            *a = try!(cursor.read_u8());
-        }
-        let netlink_family_u16 = try!(cursor.read_u16::<BigEndian>());
-        let netlink_family = netlink::NetlinkFamily::from_u16(netlink_family_u16);
-        if netlink_family.is_none() {
-            let e = io::Error::new(io::ErrorKind::Other, format!("netlink_family = {}",
-                                                                  netlink_family_u16));
-            return Err(e);
+            *a = try!(read_u8(cursor, end, "cooked header"));
         }
-        c.netlink_family = netlink_family.unwrap();
-        assert!(cursor.position() as usize == COOKED_HEADER_SIZE);
+        let netlink_family_u16 = try!(read_u16::<BigEndian>(cursor, end, "cooked header"));
+        c.netlink_family = Value::new(netlink_family_u16 as u32);
+        assert!((cursor.position() - start) as usize == COOKED_HEADER_SIZE);
 
         Ok(c)
     }
+    /// LINKTYPE_NETLINK captures from libpcap carry this header, but not every
+    /// tool that writes them does. A bare nlmsghdr starts with a native endian
+    /// nlmsg_len, which won't have ARPHRD_NETLINK in its upper half.
+    pub fn is_present(data: &[u8]) -> bool {
+        data.len() >= COOKED_HEADER_SIZE && data[2] == 0x03 && data[3] == 0x38
+    }
     fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
         let indent = format_indent(indent);
         try!(write!(f, "{{\n"));
@@ -111,9 +223,83 @@ impl CookedHeader {
     }
 }
 
+/// Cooked SLL2 header is big endian (network byte order)
+/// http://www.tcpdump.org/linktypes/LINKTYPE_LINUX_SLL2.html
+#[derive(Debug)]
+pub struct CookedHeader2 {
+    pub netlink_family: Value<netlink::NetlinkFamily>, // the protocol type field
+    pub reserved: u16,
+    pub interface_index: u32,
+    pub arphdr_type: u16,
+    pub packet_type: u8,
+    pub address_length: u8,
+    pub address: [u8; 8],
+}
+pub const COOKED_HEADER2_SIZE: usize = 20;
+impl Default for CookedHeader2 {
+    fn default() -> CookedHeader2 {
+        CookedHeader2 { netlink_family: Value::Known(netlink::NetlinkFamily::NETLINK_ROUTE),
+                        reserved: 0,
+                        interface_index: 0,
+                        arphdr_type: 0,
+                        packet_type: 0,
+                        address_length: 0,
+                        address: [0; 8] }
+    }
+}
+impl fmt::Display for CookedHeader2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.pretty_fmt(f, 0)
+    }
+}
+impl CookedHeader2 {
+    pub fn read(cursor: &mut std::io::Cursor<&[u8]>) -> error::Result<CookedHeader2> {
+        debug!("CookedHeader2::read()");
+        let mut c = CookedHeader2::default();
+        let start = cursor.position();
+        let end = start as usize + COOKED_HEADER2_SIZE;
+        try!(check_len(cursor, end, COOKED_HEADER2_SIZE, "cooked header"));
+
+        let netlink_family_u16 = try!(read_u16::<BigEndian>(cursor, end, "cooked header"));
+        c.netlink_family = Value::new(netlink_family_u16 as u32);
+        c.reserved = try!(read_u16::<BigEndian>(cursor, end, "cooked header"));
+        c.interface_index = try!(read_u32::<BigEndian>(cursor, end, "cooked header"));
+        c.arphdr_type = try!(read_u16::<BigEndian>(cursor, end, "cooked header"));
+        c.packet_type = try!(read_u8(cursor, end, "cooked header"));
+        c.address_length = try!(read_u8(cursor, end, "cooked header"));
+        for a in c.address.iter_mut() {
+            *a = try!(read_u8(cursor, end, "cooked header"));
+        }
+        assert!((cursor.position() - start) as usize == COOKED_HEADER2_SIZE);
+
+        Ok(c)
+    }
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    netlink_family: {},\n", indent, self.netlink_family));
+        try!(write!(f, "{}    interface_index: {},\n", indent, self.interface_index));
+        try!(write!(f, "{}    arphdr_type: {},\n", indent, self.arphdr_type));
+        try!(write!(f, "{}    packet_type: {},\n", indent, self.packet_type));
+        try!(write!(f, "{}    address_length: {},\n", indent, self.address_length));
+        try!(write!(f, "{}    address = [", indent));
+        let mut count: usize = 1;
+        for a in self.address.iter() {
+            try!(write!(f, " {}", a));
+            if count < self.address.len() {
+                try!(write!(f, ","));
+            }
+            count = count + 1;
+        }
+        try!(write!(f, " ],\n"));
+        write!(f, "{}}}", indent)
+    }
+}
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum NlMsgTypeEnum {
-    Raw(u16),
+    Raw(u16), // a family that we don't have message types for
+    Unknown(u16), // a family that we do, but not this one
     NlMsgType(netlink::NlMsgType),
     NrMsgType(rtnetlink::NrMsgType),
 }
@@ -122,11 +308,39 @@ impl ::std::fmt::Display for NlMsgTypeEnum {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
         match *self {
             NlMsgTypeEnum::Raw(ref u) => write!(f, "Raw({})", u),
+            NlMsgTypeEnum::Unknown(ref u) => write!(f, "Unknown({})", u),
             NlMsgTypeEnum::NlMsgType(ref u) => write!(f, "NlMsgType({})", u),
             NlMsgTypeEnum::NrMsgType(ref u) => write!(f, "NrMsgType({})", u),
         }
     }
 }
+impl ::std::str::FromStr for NlMsgTypeEnum {
+    type Err = ();
+    /// Accepts NLMSG_* and RTM_* names or a number
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(t) = netlink::NlMsgType::from_str(s) {
+            return Ok(NlMsgTypeEnum::NlMsgType(t));
+        }
+        if let Ok(t) = rtnetlink::NrMsgType::from_str(s) {
+            return Ok(NlMsgTypeEnum::NrMsgType(t));
+        }
+        match u16::from_str(s) {
+            Ok(u) => Ok(NlMsgTypeEnum::Raw(u)),
+            Err(_) => Err(()),
+        }
+    }
+}
+impl NlMsgTypeEnum {
+    /// The nlmsg_type as it was on the wire
+    pub fn value(&self) -> u16 {
+        match *self {
+            NlMsgTypeEnum::Raw(u) => u,
+            NlMsgTypeEnum::Unknown(u) => u,
+            NlMsgTypeEnum::NlMsgType(u) => u as u16,
+            NlMsgTypeEnum::NrMsgType(u) => u as u16,
+        }
+    }
+}
 impl Default for NlMsgTypeEnum {
     fn default() -> NlMsgTypeEnum {
         NlMsgTypeEnum::Raw(0)
@@ -146,38 +360,42 @@ impl fmt::Display for Nlmsghdr {
         self.pretty_fmt(f, 0)
     }
 }
+pub const NLMSGHDR_SIZE: usize = 16;
 impl Nlmsghdr {
     // Netlink header is native endian
-    // TODO: return Result
     pub fn read(cursor: &mut std::io::Cursor<&[u8]>,
-                family: netlink::NetlinkFamily) -> io::Result<Nlmsghdr> {
-        debug!("Nlmsghdr::read(..., {})", family);
+                family: Value<netlink::NetlinkFamily>) -> error::Result<Nlmsghdr> {
         let mut s = Nlmsghdr::default();
-
-        s.nlmsg_len = try!(cursor.read_u32::<NativeEndian>());
-        let nlmsg_type = try!(cursor.read_u16::<NativeEndian>());
-        s.nlmsg_type = match nlmsg_type {
+        try!(s.read_into(cursor, family));
+        Ok(s)
+    }
+    /// Like read(), but what was decoded before an error is left in self
+    pub fn read_into(&mut self, cursor: &mut std::io::Cursor<&[u8]>,
+                     family: Value<netlink::NetlinkFamily>) -> error::Result<()> {
+        debug!("Nlmsghdr::read(..., {})", family);
+        let start = cursor.position() as usize;
+        let end = start + NLMSGHDR_SIZE;
+        try!(check_len(cursor, end, NLMSGHDR_SIZE, "nlmsghdr"));
+
+        self.nlmsg_len = try!(read_u32::<NativeEndian>(cursor, end, "nlmsghdr"));
+        let nlmsg_type = try!(read_u16::<NativeEndian>(cursor, end, "nlmsghdr"));
+        self.nlmsg_flags = try!(read_u16::<NativeEndian>(cursor, end, "nlmsghdr"));
+        self.nlmsg_seq = try!(read_u32::<NativeEndian>(cursor, end, "nlmsghdr"));
+        self.nlmsg_pid = try!(read_u32::<NativeEndian>(cursor, end, "nlmsghdr"));
+        self.nlmsg_type = match nlmsg_type {
             // TODO: revisit magic numbers
             1 ... 4 => NlMsgTypeEnum::NlMsgType(netlink::NlMsgType::from_u64(nlmsg_type as u64).unwrap()),
             _ => match family {
-                netlink::NetlinkFamily::NETLINK_ROUTE => {
-                    let r = rtnetlink::NrMsgType::from_u64(nlmsg_type as u64);
-                    if r.is_none() {
-
-                        let e = io::Error::new(io::ErrorKind::Other,
-                                               format!("nlmsg_type of {} doesn't make sense for NETLINK_ROUTE",
-                                                       nlmsg_type));
-                        return Err(e);
+                Value::Known(netlink::NetlinkFamily::NETLINK_ROUTE) => {
+                    match rtnetlink::NrMsgType::from_u64(nlmsg_type as u64) {
+                        Some(r) => NlMsgTypeEnum::NrMsgType(r),
+                        None => NlMsgTypeEnum::Unknown(nlmsg_type),
                     }
-                    NlMsgTypeEnum::NrMsgType(r.unwrap())
                 }
                 _ => NlMsgTypeEnum::Raw(nlmsg_type),
             }
         };
-        s.nlmsg_flags = try!(cursor.read_u16::<NativeEndian>());
-        s.nlmsg_seq = try!(cursor.read_u32::<NativeEndian>());
-        s.nlmsg_pid = try!(cursor.read_u32::<NativeEndian>());
-        Ok(s)
+        Ok(())
     }
     fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
         let indent = format_indent(indent);
@@ -185,7 +403,7 @@ impl Nlmsghdr {
         try!(write!(f, "{}    nlmsg_len: {},\n", indent, self.nlmsg_len));
         try!(write!(f, "{}    nlmsg_type: {},\n", indent, self.nlmsg_type));
         try!(write!(f, "{}    nlmsg_flags: {:#X} (", indent, self.nlmsg_flags));
-        try!(NlMsgFlags::pretty_fmt(f, self.nlmsg_flags as u32));
+        try!(fmt_flags::<NlMsgFlags>(f, self.nlmsg_flags as u32));
         try!(write!(f, "),\n{}    nlmsg_seq: {},\n", indent, self.nlmsg_seq));
         try!(write!(f, "{}    nlmsg_pid: {},\n", indent, self.nlmsg_pid));
         write!(f, "{}}}", indent)
@@ -198,15 +416,56 @@ pub enum NlMsgEnum {
     None, // no body expected
     // TODO: replace with raw
     Unsupported, // we don't support this body type
-    MalfromedPacket, // the packet was malformed
+    /// Decoding stopped at the error. The first field has whatever was
+    /// decoded before that.
+    Malformed(Box<NlMsgEnum>, Error),
     Ifinfomsg(rtnetlink::Ifinfomsg),
     Ifaddrmsg(rtnetlink::Ifaddrmsg),
     Rtmsg(rtnetlink::Rtmsg),
     Ndmsg(rtnetlink::Ndmsg),
     Tcmsg(rtnetlink::Tcmsg),
+    Tcamsg(rtnetlink::Tcamsg),
+    FibRuleHdr(rtnetlink::FibRuleHdr),
+    /// Decoded with a spec from --spec-dir
+    Spec(spec::SpecMsg),
+}
+// Read a message body with read_into() so that we keep what was decoded
+// before an error.
+macro_rules! read_body {
+    ($t:ident, $cursor:expr, $nlmsg_len:expr) => {
+        read_body!($t, rtnetlink::$t::default(), $cursor, $nlmsg_len)
+    };
+    ($t:ident, $msg:expr, $cursor:expr, $nlmsg_len:expr) => {{
+        let mut msg = $msg;
+        match msg.read_into($cursor, $nlmsg_len) {
+            Ok(()) => NlMsgEnum::$t(msg),
+            Err(e) => NlMsgEnum::Malformed(Box::new(NlMsgEnum::$t(msg)), e),
+        }
+    }}
 }
 impl NlMsgEnum {
-    // Netlink header is native endian
+    /// The interface that this message is about, if it has one
+    pub fn ifindex(&self) -> Option<i32> {
+        match *self {
+            NlMsgEnum::Ifinfomsg(ref u) => Some(u.ifi_index),
+            NlMsgEnum::Ifaddrmsg(ref u) => Some(u.ifa_index as i32),
+            NlMsgEnum::Rtmsg(ref u) => u.oif(),
+            NlMsgEnum::Ndmsg(ref u) => Some(u.ndm_ifindex),
+            NlMsgEnum::Tcmsg(ref u) => Some(u.tcm_ifindex),
+            NlMsgEnum::Spec(ref u) => u.ifindex(),
+            NlMsgEnum::Malformed(ref u, _) => u.ifindex(),
+            _ => None,
+        }
+    }
+    /// The error that stopped decoding, if there was one
+    pub fn error(&self) -> Option<&Error> {
+        match *self {
+            NlMsgEnum::Malformed(_, ref e) => Some(e),
+            _ => None,
+        }
+    }
+    /// Read the body of a message that ends at `nlmsg_len` (an absolute
+    /// position in the cursor).
     pub fn read(cursor: &mut std::io::Cursor<&[u8]>,
                nlmsg_type: NlMsgTypeEnum,
                nlmsg_len: usize) -> NlMsgEnum {
@@ -217,68 +476,58 @@ impl NlMsgEnum {
                 if *u == rtnetlink::NrMsgType::RTM_NEWLINK ||
                    *u == rtnetlink::NrMsgType::RTM_DELLINK ||
                    *u == rtnetlink::NrMsgType::RTM_GETLINK {
-                    let o = rtnetlink::Ifinfomsg::read(cursor, nlmsg_len);
-                    match o {
-                        Ok(msg) => NlMsgEnum::Ifinfomsg(msg),
-                        _ => NlMsgEnum::MalfromedPacket
-                    }
+                    read_body!(Ifinfomsg, cursor, nlmsg_len)
                 }
                 else if *u == rtnetlink::NrMsgType::RTM_NEWADDR ||
                    *u == rtnetlink::NrMsgType::RTM_DELADDR ||
                    *u == rtnetlink::NrMsgType::RTM_GETADDR {
-                    let o = rtnetlink::Ifaddrmsg::read(cursor, nlmsg_len);
-                    match o {
-                        Ok(msg) => NlMsgEnum::Ifaddrmsg(msg),
-                        _ => NlMsgEnum::MalfromedPacket
-                    }
+                    read_body!(Ifaddrmsg, cursor, nlmsg_len)
                 }
                 else if *u == rtnetlink::NrMsgType::RTM_NEWROUTE ||
                    *u == rtnetlink::NrMsgType::RTM_DELROUTE ||
                    *u == rtnetlink::NrMsgType::RTM_GETROUTE {
-                    let o = rtnetlink::Rtmsg::read(cursor, nlmsg_len);
-                    match o {
-                        Ok(msg) => NlMsgEnum::Rtmsg(msg),
-                        _ => NlMsgEnum::MalfromedPacket
-                    }
+                    read_body!(Rtmsg, cursor, nlmsg_len)
                 }
                 else if *u == rtnetlink::NrMsgType::RTM_NEWNEIGH ||
                    *u == rtnetlink::NrMsgType::RTM_DELNEIGH ||
                    *u == rtnetlink::NrMsgType::RTM_GETNEIGH {
-                    let o = rtnetlink::Ndmsg::read(cursor, nlmsg_len);
-                    match o {
-                        Ok(msg) => NlMsgEnum::Ndmsg(msg),
-                        _ => NlMsgEnum::MalfromedPacket
-                    }
+                    read_body!(Ndmsg, cursor, nlmsg_len)
                 }
                 else if *u == rtnetlink::NrMsgType::RTM_NEWQDISC ||
                    *u == rtnetlink::NrMsgType::RTM_DELQDISC ||
                    *u == rtnetlink::NrMsgType::RTM_GETQDISC ||
                    *u == rtnetlink::NrMsgType::RTM_NEWTCLASS ||
                    *u == rtnetlink::NrMsgType::RTM_DELTCLASS ||
-                   *u == rtnetlink::NrMsgType::RTM_GETTCLASS ||
-                   *u == rtnetlink::NrMsgType::RTM_NEWTFILTER ||
+                   *u == rtnetlink::NrMsgType::RTM_GETTCLASS {
+                    read_body!(Tcmsg, cursor, nlmsg_len)
+                }
+                else if *u == rtnetlink::NrMsgType::RTM_NEWTFILTER ||
                    *u == rtnetlink::NrMsgType::RTM_DELTFILTER ||
                    *u == rtnetlink::NrMsgType::RTM_GETTFILTER {
-                    let o = rtnetlink::Tcmsg::read(cursor, nlmsg_len);
-                    match o {
-                        Ok(msg) => NlMsgEnum::Tcmsg(msg),
-                        _ => NlMsgEnum::MalfromedPacket
-                    }
+                    let filter = rtnetlink::Tcmsg { tcm_filter: true, ..Default::default() };
+                    read_body!(Tcmsg, filter, cursor, nlmsg_len)
+                }
+                else if *u == rtnetlink::NrMsgType::RTM_NEWACTION ||
+                   *u == rtnetlink::NrMsgType::RTM_DELACTION ||
+                   *u == rtnetlink::NrMsgType::RTM_GETACTION {
+                    read_body!(Tcamsg, cursor, nlmsg_len)
+                }
+                else if *u == rtnetlink::NrMsgType::RTM_NEWRULE ||
+                   *u == rtnetlink::NrMsgType::RTM_DELRULE ||
+                   *u == rtnetlink::NrMsgType::RTM_GETRULE {
+                    read_body!(FibRuleHdr, cursor, nlmsg_len)
                 }
                 else {
-                    let pos = cursor.position();
-                    cursor.set_position(pos + nlmsg_len as u64);
+                    cursor.set_position(nlmsg_len as u64);
                     NlMsgEnum::default()
                 }
             },
             NlMsgTypeEnum::NlMsgType(_) => {
-                let pos = cursor.position();
-                cursor.set_position(pos + nlmsg_len as u64);
+                cursor.set_position(nlmsg_len as u64);
                 NlMsgEnum::None
             },
             _ => {
-                let pos = cursor.position();
-                cursor.set_position(pos + nlmsg_len as u64);
+                cursor.set_position(nlmsg_len as u64);
                 NlMsgEnum::default()
             }
         }
@@ -288,7 +537,11 @@ impl NlMsgEnum {
         match *self {
             NlMsgEnum::None => return write!(f, "None"),
             NlMsgEnum::Unsupported => return write!(f, "Unsupported"),
-            NlMsgEnum::MalfromedPacket => return write!(f, "MalfromedPacket"),
+            NlMsgEnum::Malformed(ref u, ref e) => {
+                try!(write!(f, "Malformed( "));
+                try!(u.pretty_fmt(f, indent+1));
+                return write!(f, ",\n{}    error: {} )", format_indent(indent), e);
+            }
             _ => {},
         }
 
@@ -313,6 +566,18 @@ impl NlMsgEnum {
                 try!(write!(f, "Tcmsg( "));
                 try!(u.pretty_fmt(f, indent+1));
             }
+            NlMsgEnum::Tcamsg(ref u) => {
+                try!(write!(f, "Tcamsg( "));
+                try!(u.pretty_fmt(f, indent+1));
+            }
+            NlMsgEnum::FibRuleHdr(ref u) => {
+                try!(write!(f, "FibRuleHdr( "));
+                try!(u.pretty_fmt(f, indent+1));
+            }
+            NlMsgEnum::Spec(ref u) => {
+                try!(write!(f, "Spec( "));
+                try!(u.pretty_fmt(f, indent+1));
+            }
             _ => {},
         }
         write!(f, " )")
@@ -336,36 +601,130 @@ const NLMSG_ALIGNTO: u64 = 4;
 // TODO: wrap in Enum? Or just better error handling below?
 #[derive(Debug, Default)]
 pub struct NlMsg {
-    pub netlink_family: netlink::NetlinkFamily,
+    pub netlink_family: Value<netlink::NetlinkFamily>,
     pub nlmsghdr: Nlmsghdr,
     pub nlmsg: NlMsgEnum,
 }
 impl NlMsg
 {
-    pub fn read(data: &[u8]) -> Vec<NlMsg> {
+    /// Read the netlink messages after a cooked header. It is only an error
+    /// if the cooked header is cut off. A family we don't know is kept as
+    /// Value::Unknown, and other problems are reported per message with
+    /// NlMsgEnum::Malformed.
+    pub fn read(data: &[u8]) -> error::Result<Vec<NlMsg>> {
+        NlMsg::read_with(data, None)
+    }
+    fn read_with(data: &[u8], specs: Option<&spec::SpecSet>) -> error::Result<Vec<NlMsg>> {
         debug!("NlMsg::read()");
-        let mut vec: Vec<NlMsg> = Vec::new();
         let mut cursor = Cursor::new(data);
-        let cookedheader = CookedHeader::read(&mut cursor).unwrap();
+        let cookedheader = try!(CookedHeader::read(&mut cursor));
+
+        Ok(NlMsg::read_msgs(&mut cursor, cookedheader.netlink_family, specs))
+    }
+    /// Read the netlink messages in a packet captured with `link_type`.
+    /// `family` is only used when there is no pseudo-header to tell us the
+    /// netlink family. Anything that isn't netlink gives an empty Vec.
+    pub fn read_link_type(data: &[u8], link_type: netlink::LinkType,
+                          family: netlink::NetlinkFamily) -> error::Result<Vec<NlMsg>> {
+        NlMsg::read_link_type_with(data, link_type, family, None)
+    }
+    /// Like read_link_type(), but messages that one of `specs` covers are
+    /// decoded with it.
+    pub fn read_link_type_with(data: &[u8], link_type: netlink::LinkType,
+                               family: netlink::NetlinkFamily,
+                               specs: Option<&spec::SpecSet>) -> error::Result<Vec<NlMsg>> {
+        debug!("NlMsg::read_link_type(..., {}, {})", link_type, family);
+        match link_type {
+            netlink::LinkType::LINKTYPE_LINUX_SLL => NlMsg::read_with(data, specs),
+            netlink::LinkType::LINKTYPE_LINUX_SLL2 => {
+                let mut cursor = Cursor::new(data);
+                let c = try!(CookedHeader2::read(&mut cursor));
+                Ok(NlMsg::read_msgs(&mut cursor, c.netlink_family, specs))
+            }
+            netlink::LinkType::LINKTYPE_NETLINK => {
+                if CookedHeader::is_present(data) {
+                    NlMsg::read_with(data, specs)
+                }
+                else {
+                    let mut cursor = Cursor::new(data);
+                    Ok(NlMsg::read_msgs(&mut cursor, Value::Known(family), specs))
+                }
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+    /// Read netlink messages that don't have a cooked header in front of
+    /// them, eg. a buffer straight from recv() on an AF_NETLINK socket.
+    pub fn read_raw(data: &[u8], family: netlink::NetlinkFamily) -> Vec<NlMsg> {
+        debug!("NlMsg::read_raw(..., {})", family);
+        let mut cursor = Cursor::new(data);
 
-        while (cursor.position() as usize) < data.len() {
-            let cur_pos = cursor.position();
-            debug!("in NlMsg::read cur_pos = {} data.len() = {}", cur_pos, data.len());
+        NlMsg::read_msgs(&mut cursor, Value::Known(family), None)
+    }
+    fn read_msgs(cursor: &mut std::io::Cursor<&[u8]>,
+                 family: Value<netlink::NetlinkFamily>,
+                 specs: Option<&spec::SpecSet>) -> Vec<NlMsg> {
+        let mut vec: Vec<NlMsg> = Vec::new();
+        let data_len = get_size(cursor) as usize;
+
+        while (cursor.position() as usize) < data_len {
+            let cur_pos = cursor.position() as usize;
+            debug!("in NlMsg::read cur_pos = {} data.len() = {}", cur_pos, data_len);
             let mut nlmsg = NlMsg::default();
-            nlmsg.netlink_family = cookedheader.netlink_family;
-            let nlmsghdr = Nlmsghdr::read(&mut cursor, cookedheader.netlink_family);
-            if nlmsghdr.is_err() {
+            nlmsg.netlink_family = family;
+            let r = nlmsg.nlmsghdr.read_into(cursor, family);
+            info!("in NlMsg::read() nlmsg.nlmsghdr = {:?}", nlmsg.nlmsghdr);
+            let nlmsg_len = nlmsg.nlmsghdr.nlmsg_len as usize;
+            let available = data_len - cur_pos;
+            if let Err(e) = r {
+                // without a whole header there is no way to find the next message
+                nlmsg.nlmsg = NlMsgEnum::Malformed(Box::new(NlMsgEnum::None), e);
+                vec.push(nlmsg);
                 break;
             }
-            nlmsg.nlmsghdr = nlmsghdr.unwrap();
-            info!("in NlMsg::read() nlmsg.nlmsghdr = {:?}", nlmsg.nlmsghdr);
-            let len: usize = nlmsg.nlmsghdr.nlmsg_len as usize + cur_pos as usize;
-            nlmsg.nlmsg = NlMsgEnum::read(&mut cursor, nlmsg.nlmsghdr.nlmsg_type, len);
+            if nlmsg_len < NLMSGHDR_SIZE {
+                let e = Error::new(ErrorKind::BadNlmsgLen { nlmsg_len: nlmsg_len as u32,
+                                                            available: available },
+                                   cur_pos);
+                nlmsg.nlmsg = NlMsgEnum::Malformed(Box::new(NlMsgEnum::None), e);
+                vec.push(nlmsg);
+                break;
+            }
+            if nlmsg_len > available {
+                // decode what we have, but this is the last message we can find
+                let e = Error::new(ErrorKind::BadNlmsgLen { nlmsg_len: nlmsg_len as u32,
+                                                            available: available },
+                                   cur_pos);
+                let partial = match NlMsg::read_body(cursor, family, &nlmsg.nlmsghdr,
+                                                     data_len, specs) {
+                    NlMsgEnum::Malformed(u, _) => u,
+                    u => Box::new(u),
+                };
+                nlmsg.nlmsg = NlMsgEnum::Malformed(partial, e);
+                vec.push(nlmsg);
+                break;
+            }
+            let end = cur_pos + nlmsg_len;
+            nlmsg.nlmsg = NlMsg::read_body(cursor, family, &nlmsg.nlmsghdr, end, specs);
             vec.push(nlmsg);
-            NlMsg::nlmsg_align(&mut cursor);
+            // after a malformed body, pick up at the next message
+            cursor.set_position(end as u64);
+            NlMsg::nlmsg_align(cursor);
         }
         vec
     }
+    /// A spec that covers the message wins over the compiled-in decoders
+    fn read_body(cursor: &mut std::io::Cursor<&[u8]>, family: Value<netlink::NetlinkFamily>,
+                 nlmsghdr: &Nlmsghdr, end: usize,
+                 specs: Option<&spec::SpecSet>) -> NlMsgEnum {
+        if let Some(specs) = specs {
+            if let Some(m) = specs.read(cursor, family, nlmsghdr.nlmsg_type.value(),
+                                        nlmsghdr.nlmsg_flags, end) {
+                return m;
+            }
+        }
+        NlMsgEnum::read(cursor, nlmsghdr.nlmsg_type, end)
+    }
     /// This function lets you align the cursor to the next NLMSG_ALIGNTO (4)
     /// byte boundry.
     fn nlmsg_align(cursor: &mut std::io::Cursor<&[u8]>) {
@@ -418,7 +777,7 @@ fn test_cookedheader_read() {
     for a in h.address.iter() {
         assert!(*a == 0);
     }
-    assert!(h.netlink_family == netlink::NetlinkFamily::NETLINK_GENERIC);
+    assert!(h.netlink_family == Value::Known(netlink::NetlinkFamily::NETLINK_GENERIC));
 }
 
 #[test]
@@ -427,7 +786,7 @@ fn test_nlmsghdr_read() {
                     0, 0, 26, 0, 5, 3, 89, 7, 185, 85, 249, 2, 128, 0, 32, 0,
                     0, 0, 8, 0, 3, 0, 2, 0, 0, 0, 8, 0, 1, 0, 0, 0, 0, 0];
     let mut cursor = Cursor::new(&raw_data[COOKED_HEADER_SIZE ..] as &[u8]);
-    let h = Nlmsghdr::read(&mut cursor, netlink::NetlinkFamily::NETLINK_GENERIC).unwrap();
+    let h = Nlmsghdr::read(&mut cursor, Value::Known(netlink::NetlinkFamily::NETLINK_GENERIC)).unwrap();
     println!("h = {:?}", h);
 
     assert!(h.nlmsg_len == 36);
@@ -442,11 +801,11 @@ fn test_NlMsg_read() {
     let raw_data = [0u8, 4, 3, 56, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 16, 36, 0,
                     0, 0, 26, 0, 5, 3, 89, 7, 185, 85, 249, 2, 128, 0, 32, 0,
                     0, 0, 8, 0, 3, 0, 2, 0, 0, 0, 8, 0, 1, 0, 0, 0, 0, 0];
-    let v = NlMsg::read(&raw_data);
+    let v = NlMsg::read(&raw_data).unwrap();
     let ref msg = v[0];
     println!("msg = {:?}", msg);
 
-    assert!(msg.netlink_family == netlink::NetlinkFamily::NETLINK_GENERIC);
+    assert!(msg.netlink_family == Value::Known(netlink::NetlinkFamily::NETLINK_GENERIC));
     assert!(msg.nlmsghdr.nlmsg_len == 36);
     assert!(msg.nlmsghdr.nlmsg_type == NlMsgTypeEnum::Raw(26));
     assert!(msg.nlmsghdr.nlmsg_flags == 773);
@@ -454,6 +813,137 @@ fn test_NlMsg_read() {
     assert!(msg.nlmsghdr.nlmsg_pid == 8389369);
 }
 
+#[test]
+fn test_NlMsg_read_truncated() {
+    let raw_data = [0u8, 4, 3, 56, 0, 0, 0, 0, 0, 0];
+    let e = NlMsg::read(&raw_data).unwrap_err();
+    assert!(e == Error::new(ErrorKind::Truncated("cooked header"), 0));
+
+    // a whole cooked header, but the nlmsghdr is cut off
+    let raw_data = [0u8, 4, 3, 56, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 36, 0, 0, 0, 16];
+    let v = NlMsg::read(&raw_data).unwrap();
+    assert!(v.len() == 1);
+    assert!(v[0].nlmsg.error() == Some(&Error::new(ErrorKind::Truncated("nlmsghdr"), 16)));
+}
+
+#[test]
+fn test_NlMsg_read_malformed() {
+    // RTM_NEWLINK with IFLA_MTU, then an IFLA_IFNAME with rta_len running
+    // past the end of the message
+    let raw_data = [44u8, 0, 0, 0, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    8, 0, 4, 0, 220, 5, 0, 0, 40, 0, 3, 0];
+    let v = NlMsg::read_raw(&raw_data, netlink::NetlinkFamily::NETLINK_ROUTE);
+    assert!(v.len() == 1);
+    match v[0].nlmsg {
+        NlMsgEnum::Malformed(ref partial, ref e) => {
+            assert!(*e == Error::new(ErrorKind::BadRtaLen { rta_len: 40, available: 4 }, 40));
+            match **partial {
+                NlMsgEnum::Ifinfomsg(ref u) => {
+                    assert!(u.ifi_index == 2);
+                    assert!(u.ifi_attr.len() == 1);
+                }
+                _ => panic!("expected a partial Ifinfomsg"),
+            }
+        }
+        _ => panic!("expected Malformed"),
+    }
+
+    // nlmsg_len too small to be real
+    let raw_data = [8u8, 0, 0, 0, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let v = NlMsg::read_raw(&raw_data, netlink::NetlinkFamily::NETLINK_ROUTE);
+    assert!(v.len() == 1);
+    assert!(v[0].nlmsg.error() ==
+            Some(&Error::new(ErrorKind::BadNlmsgLen { nlmsg_len: 8, available: 16 }, 0)));
+}
+
+#[test]
+fn test_NlMsg_read_unknown_values() {
+    // RTM_NEWLINK with IFF_UP and an unnamed flag bit, and attribute 67
+    let mut raw_data = vec![40u8, 0, 0, 0, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                            0, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 64, 0, 0, 0, 0,
+                            8, 0, 67, 0, 1, 0, 0, 0];
+    // a message type that NETLINK_ROUTE doesn't have
+    raw_data.extend_from_slice(&[16u8, 0, 0, 0, 15, 39, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    // RTM_NEWROUTE from routing protocol 250
+    raw_data.extend_from_slice(&[28u8, 0, 0, 0, 24, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                                 2, 0, 0, 0, 254, 250, 0, 1, 0, 0, 0, 0]);
+    let v = NlMsg::read_raw(&raw_data, netlink::NetlinkFamily::NETLINK_ROUTE);
+    assert!(v.len() == 3);
+    match v[0].nlmsg {
+        NlMsgEnum::Ifinfomsg(ref u) => {
+            assert!(u.ifi_attr.len() == 1);
+            assert!(u.ifi_attr[0].rta_type == Value::Unknown(67));
+            let s = format!("{}", u);
+            assert!(s.contains("rta_type: Unknown(67)"));
+            assert!(s.contains("IFF_UP|0x40000000"));
+        }
+        _ => panic!("expected an Ifinfomsg"),
+    }
+    assert!(v[1].nlmsghdr.nlmsg_type == NlMsgTypeEnum::Unknown(9999));
+    assert!(v[1].nlmsg.error().is_none());
+    match v[2].nlmsg {
+        NlMsgEnum::Rtmsg(ref u) => {
+            assert!(u.rtm_protocol == Value::Unknown(250));
+            assert!(u.rtm_type == Value::Known(rtnetlink::Rtn::RTN_UNICAST));
+        }
+        _ => panic!("expected an Rtmsg"),
+    }
+}
+
+#[test]
+fn test_NlMsg_read_raw() {
+    let raw_data = [36u8, 0, 0, 0, 26, 0, 5, 3, 89, 7, 185, 85, 249, 2, 128, 0,
+                    32, 0, 0, 0, 8, 0, 3, 0, 2, 0, 0, 0, 8, 0, 1, 0, 0, 0, 0, 0];
+    let v = NlMsg::read_raw(&raw_data, netlink::NetlinkFamily::NETLINK_GENERIC);
+    assert!(v.len() == 1);
+    let ref msg = v[0];
+
+    assert!(msg.netlink_family == Value::Known(netlink::NetlinkFamily::NETLINK_GENERIC));
+    assert!(msg.nlmsghdr.nlmsg_len == 36);
+    assert!(msg.nlmsghdr.nlmsg_type == NlMsgTypeEnum::Raw(26));
+    assert!(msg.nlmsghdr.nlmsg_seq == 1438189401);
+}
+
+#[test]
+fn test_NlMsg_read_link_type() {
+    let sll = [0u8, 4, 3, 56, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 16, 36, 0,
+               0, 0, 26, 0, 5, 3, 89, 7, 185, 85, 249, 2, 128, 0, 32, 0,
+               0, 0, 8, 0, 3, 0, 2, 0, 0, 0, 8, 0, 1, 0, 0, 0, 0, 0];
+    let sll2 = [0u8, 16, 0, 0, 0, 0, 0, 7, 3, 56, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                36, 0, 0, 0, 26, 0, 5, 3, 89, 7, 185, 85, 249, 2, 128, 0, 32, 0,
+                0, 0, 8, 0, 3, 0, 2, 0, 0, 0, 8, 0, 1, 0, 0, 0, 0, 0];
+    let check = |v: error::Result<Vec<NlMsg>>| {
+        let v = v.unwrap();
+        assert!(v.len() == 1);
+        assert!(v[0].netlink_family == Value::Known(netlink::NetlinkFamily::NETLINK_GENERIC));
+        assert!(v[0].nlmsghdr.nlmsg_len == 36);
+        assert!(v[0].nlmsghdr.nlmsg_type == NlMsgTypeEnum::Raw(26));
+        assert!(v[0].nlmsghdr.nlmsg_pid == 8389369);
+    };
+
+    let route = netlink::NetlinkFamily::NETLINK_ROUTE;
+    let generic = netlink::NetlinkFamily::NETLINK_GENERIC;
+    check(NlMsg::read_link_type(&sll, netlink::LinkType::LINKTYPE_LINUX_SLL, route));
+    check(NlMsg::read_link_type(&sll, netlink::LinkType::LINKTYPE_NETLINK, route));
+    check(NlMsg::read_link_type(&sll2, netlink::LinkType::LINKTYPE_LINUX_SLL2, route));
+    check(NlMsg::read_link_type(&sll[COOKED_HEADER_SIZE ..],
+                                netlink::LinkType::LINKTYPE_NETLINK, generic));
+    assert!(NlMsg::read_link_type(&sll, netlink::LinkType::LINKTYPE_ETHERNET, route).unwrap().len() == 0);
+}
+
+#[test]
+fn test_NlMsgTypeEnum_from_str() {
+    use ::std::str::FromStr;
+    let t = NlMsgTypeEnum::from_str("RTM_NEWLINK").unwrap();
+    assert!(t == NlMsgTypeEnum::NrMsgType(rtnetlink::NrMsgType::RTM_NEWLINK));
+    assert!(t.value() == 16);
+    let t = NlMsgTypeEnum::from_str("NLMSG_ERROR").unwrap();
+    assert!(t == NlMsgTypeEnum::NlMsgType(netlink::NlMsgType::NLMSG_ERROR));
+    assert!(NlMsgTypeEnum::from_str("26").unwrap() == NlMsgTypeEnum::Raw(26));
+    assert!(NlMsgTypeEnum::from_str("RTM_BOGUS").is_err());
+}
+
 #[test]
 fn test_NlMsg_nlmsg_align() {
     let raw_data = [0u8, 0, 0, 0, 0, 0, 0, 0];
@@ -2,3 +2,5 @@
 include!(concat!(env!("OUT_DIR"), "/netlink_family.rs"));
 // this is where the NlMsgType enum was generated by build.rs
 include!(concat!(env!("OUT_DIR"), "/nl_msg_type.rs"));
+// this is where the LinkType enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/link_type.rs"));
@@ -1,10 +1,12 @@
-use ::std::io;
-use ::std::io::prelude::*;
-use ::std::io::{Cursor, Result};
-use ::byteorder::{NativeEndian, ReadBytesExt};
+use ::std::io::Cursor;
+use ::std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use ::byteorder::{BigEndian, ByteOrder, NativeEndian, ReadBytesExt};
 use ::num::FromPrimitive;
 use ::std::fmt;
-use nl::{format_indent, NlMsg};
+use ::std::marker::PhantomData;
+use nl::{format_indent, fmt_flags, NlMsg, Pretty, Value, Error, ErrorKind};
+use nl::error;
+use nl::{check_len, read_u8, read_u16, read_u32, read_u64, read_i32, read_bytes};
 
 // this is where the NetDeviceFlags enum was generated by build.rs
 include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/net_device_flags.rs"));
@@ -16,6 +18,56 @@ include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifa_flags.rs"));
 include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifa.rs"));
 // this is where the Ifla enum was generated by build.rs
 include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla.rs"));
+// this is where the IfOper enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/if_oper.rs"));
+// this is where the IfLinkMode enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/if_link_mode.rs"));
+// this is where the IflaInfo enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_info.rs"));
+// this is where the IflaVlan enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_vlan.rs"));
+// this is where the IflaMacvlan enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_macvlan.rs"));
+// this is where the MacvlanMode enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/macvlan_mode.rs"));
+// this is where the IflaVrf enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_vrf.rs"));
+// this is where the IflaIpvlan enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_ipvlan.rs"));
+// this is where the IpvlanMode enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ipvlan_mode.rs"));
+// this is where the VethInfo enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/veth_info.rs"));
+// this is where the IflaVxlan enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_vxlan.rs"));
+// this is where the IflaGeneve enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_geneve.rs"));
+// this is where the IflaGre enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_gre.rs"));
+// this is where the IflaBond enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_bond.rs"));
+// this is where the BondMode enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/bond_mode.rs"));
+// this is where the IflaBondSlave enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_bond_slave.rs"));
+// this is where the IflaBr enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_br.rs"));
+// this is where the IflaBrport enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_brport.rs"));
+// this is where the BrState enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/br_state.rs"));
+// this is where the IflaInet enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_inet.rs"));
+// this is where the IflaInet6 enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_inet6.rs"));
+// this is where the In6AddrGenMode enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/in6_addr_gen_mode.rs"));
+// this is where the IflaBridge enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ifla_bridge.rs"));
+// this is where the BridgeMode enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/bridge_mode.rs"));
+// this is where the BridgeVlanInfoFlags enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/bridge_vlan_info_flags.rs"));
 // this is where the Rtn enum was generated by build.rs
 include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/rtn.rs"));
 // this is where the Rtprot enum was generated by build.rs
@@ -28,6 +80,32 @@ include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/rt_table.rs"));
 include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/rtm_flags.rs"));
 // this is where the RtmAttr enum was generated by build.rs
 include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/rtm_attr.rs"));
+// this is where the Rtax enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/rtax.rs"));
+// this is where the RtnhFlags enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/rtnh_flags.rs"));
+// this is where the RtaPref enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/rta_pref.rs"));
+// this is where the LwtunnelEncapType enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/lwtunnel_encap_type.rs"));
+// this is where the MplsIptunnel enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/mpls_iptunnel.rs"));
+// this is where the LwtunnelIp enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/lwtunnel_ip.rs"));
+// this is where the LwtunnelIp6 enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/lwtunnel_ip6.rs"));
+// this is where the Seg6Iptunnel enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/seg6_iptunnel.rs"));
+// this is where the Seg6IptunMode enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/seg6_iptun_mode.rs"));
+// this is where the Seg6Local enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/seg6_local.rs"));
+// this is where the Seg6LocalAction enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/seg6_local_action.rs"));
+// this is where the LwtBpf enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/lwt_bpf.rs"));
+// this is where the LwtBpfProg enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/lwt_bpf_prog.rs"));
 // this is where the NdState enum was generated by build.rs
 include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/nd_state.rs"));
 // this is where the NdFlags enum was generated by build.rs
@@ -36,76 +114,547 @@ include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/nd_flags.rs"));
 include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/nd_attr.rs"));
 // this is where the TcAttr enum was generated by build.rs
 include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tc_attr.rs"));
+// this is where the TcaHtb enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_htb.rs"));
+// this is where the TcaTbf enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_tbf.rs"));
+// this is where the TcaFqCodel enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_fq_codel.rs"));
+// this is where the TcaFq enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_fq.rs"));
+// this is where the TcaNetem enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_netem.rs"));
+// this is where the TcaStats enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_stats.rs"));
+// this is where the TcaStab enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_stab.rs"));
+// this is where the EthP enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/eth_p.rs"));
+// this is where the IpProto enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/ip_proto.rs"));
+// this is where the TcaClsFlags enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_cls_flags.rs"));
+// this is where the TcaU32 enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_u32.rs"));
+// this is where the TcU32Flags enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tc_u32_flags.rs"));
+// this is where the TcaFlower enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_flower.rs"));
+// this is where the TcaBpf enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_bpf.rs"));
+// this is where the TcaMatchall enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_matchall.rs"));
+// this is where the TcaAct enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_act.rs"));
+// this is where the TcAct enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tc_act.rs"));
+// this is where the TcaGact enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_gact.rs"));
+// this is where the TcaMirred enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_mirred.rs"));
+// this is where the TcaMirredEaction enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_mirred_eaction.rs"));
+// this is where the TcaPedit enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_pedit.rs"));
+// this is where the TcaVlan enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_vlan.rs"));
+// this is where the TcaVlanAct enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_vlan_act.rs"));
+// this is where the TcaTunnelKey enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_tunnel_key.rs"));
+// this is where the TcaTunnelKeyAct enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_tunnel_key_act.rs"));
+// this is where the TcaSkbedit enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_skbedit.rs"));
+// this is where the TcaRoot enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/tca_root.rs"));
+// this is where the Fra enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/fra.rs"));
+// this is where the FrAct enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/fr_act.rs"));
+// this is where the FibRuleFlags enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/fib_rule_flags.rs"));
 // this is where the NrMsgType enum was generated by build.rs
 include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/nr_msg_type.rs"));
+// this is where the RtnlGrp enum was generated by build.rs
+include!(concat!(env!("OUT_DIR"), "/rtnetlink", "/rtnlgrp.rs"));
 
 /* TODO:
 - concistant naming of messages
 - concistant naming of attributes
 - better error handling (option vs Err)
-- better attribute handling (struct rtnl_link_stats, etc)
 - one more indent for all attrs
 - move code around, especially generated code, to make things more readable
 - more robustness for Rtprot? Theoretically users could use other values.
 */
 
+/// Set in rta_type when the payload is more attributes
+pub const NLA_F_NESTED: u16 = 1 << 15;
+/// Set in rta_type when the payload is in network byte order
+pub const NLA_F_NET_BYTEORDER: u16 = 1 << 14;
+/// The part of rta_type that is the type
+pub const NLA_TYPE_MASK: u16 = !(NLA_F_NESTED | NLA_F_NET_BYTEORDER);
+
+// this is where build.rs put the Nested enum, and the AttrType impls for
+// the enums that have an [attr-types] table in their .toml
+include!(concat!(env!("OUT_DIR"), "/attr_types.rs"));
+
+/// What the payload of an attribute is. Which attribute has which payload
+/// comes from the [attr-types] tables in build_input.
+#[derive(Copy, Clone)]
+pub enum PayloadType {
+    Bytes,
+    U8,
+    U16,
+    U32,
+    U64,
+    /// A signed u32, for the ints that are -1 when they aren't set
+    S32,
+    Be16,
+    Be32,
+    Be64,
+    /// Usually with a trailing NUL
+    String,
+    /// A hardware address. Usually 6 bytes, but not always.
+    Mac,
+    /// IPv4 or IPv6, by its length
+    Ip,
+    /// An ID that is only bytes, printed as one hex number
+    Hex,
+    /// No payload, the attribute being there is what it says
+    Flag,
+    /// A number, 1, 2 or 4 bytes, that an enum has names for
+    Enum(usize, fn(u32) -> String),
+    /// Like Enum, but big endian, like an ethertype
+    BeEnum(usize, fn(u32) -> String),
+    /// An array of s32, with a name for each index, like the devconf arrays
+    S32s(&'static [&'static str]),
+    /// An array of u16, like the size table of TCA_STAB
+    U16s,
+    /// An array of u32, like the rate tables of htb and tbf
+    U32s,
+    /// An array of u64 counters
+    U64s,
+    /// RTA_MULTIPATH's struct rtnexthops, each with its own attributes
+    Nexthops,
+    /// An MPLS label stack, big endian label stack entries
+    Mpls,
+    /// A u32 that is a qdisc or class handle, like TCA_U32_CLASSID
+    Handle,
+    /// More attributes
+    Nested(fn(&mut Cursor<&[u8]>, usize, Value<AddressFamily>) -> error::Result<Nested>),
+    /// A C struct
+    Struct(fn(&mut Cursor<&[u8]>, usize) -> error::Result<Box<AttrStruct>>),
+}
+
+/// A C struct that is the payload of an attribute
+pub trait AttrStruct: Pretty + fmt::Debug {
+    fn box_clone(&self) -> Box<AttrStruct>;
+}
+impl Clone for Box<AttrStruct> {
+    fn clone(&self) -> Box<AttrStruct> {
+        self.box_clone()
+    }
+}
+
+/// The payload of an attribute, decoded. Bytes means that it is only in
+/// rta_value, either because we don't know what it is, or it wasn't the
+/// size that it should have been.
+#[derive(Debug, Clone)]
+pub enum Payload {
+    Bytes,
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    S32(i32),
+    String(String),
+    Mac(Vec<u8>),
+    Ip(IpAddr),
+    /// An address and its prefix length, which came from the message
+    /// header, eg. IFA_ADDRESS with ifa_prefixlen
+    Prefix(IpAddr, u8),
+    Hex(Vec<u8>),
+    Flag,
+    /// The number and its name
+    Enum(u32, String),
+    /// The numbers and the names for them by index
+    S32s(Vec<i32>, &'static [&'static str]),
+    U16s(Vec<u16>),
+    U32s(Vec<u32>),
+    U64s(Vec<u64>),
+    Nexthops(Vec<Rtnexthop>),
+    /// The label stack entries, top of the stack first
+    Mpls(Vec<u32>),
+    Handle(TcHandle),
+    Nested(Nested),
+    Struct(Box<AttrStruct>),
+}
+impl Default for Payload {
+    fn default() -> Payload {
+        Payload::Bytes
+    }
+}
+impl Payload {
+    /// Decode `value`, which starts at the cursor. Numbers are native endian
+    /// unless the type is Be16/Be32 or NLA_F_NET_BYTEORDER was set.
+    fn read(t: PayloadType, cursor: &mut Cursor<&[u8]>, value: &[u8],
+            net_byteorder: bool, family: Value<AddressFamily>) -> error::Result<Payload> {
+        let end = cursor.position() as usize + value.len();
+        let p = match (t, value.len()) {
+            (PayloadType::U8, 1) => Payload::U8(value[0]),
+            (PayloadType::U16, 2) if net_byteorder => Payload::U16(BigEndian::read_u16(value)),
+            (PayloadType::U16, 2) => Payload::U16(NativeEndian::read_u16(value)),
+            (PayloadType::U32, 4) if net_byteorder => Payload::U32(BigEndian::read_u32(value)),
+            (PayloadType::U32, 4) => Payload::U32(NativeEndian::read_u32(value)),
+            (PayloadType::U64, 8) if net_byteorder => Payload::U64(BigEndian::read_u64(value)),
+            (PayloadType::U64, 8) => Payload::U64(NativeEndian::read_u64(value)),
+            (PayloadType::S32, 4) => Payload::S32(NativeEndian::read_i32(value)),
+            (PayloadType::Be16, 2) => Payload::U16(BigEndian::read_u16(value)),
+            (PayloadType::Be32, 4) => Payload::U32(BigEndian::read_u32(value)),
+            (PayloadType::Be64, 8) => Payload::U64(BigEndian::read_u64(value)),
+            (PayloadType::Handle, 4) => Payload::Handle(TcHandle(NativeEndian::read_u32(value))),
+            (PayloadType::Mpls, n) if n > 0 && n % 4 == 0 => {
+                Payload::Mpls(value.chunks(4).map(BigEndian::read_u32).collect())
+            }
+            (PayloadType::String, _) => {
+                let s = match value.iter().position(|b| *b == 0) {
+                    Some(nul) => &value[..nul],
+                    None => value,
+                };
+                Payload::String(String::from_utf8_lossy(s).into_owned())
+            }
+            (PayloadType::Mac, n) if n > 0 => Payload::Mac(value.to_vec()),
+            (PayloadType::Hex, n) if n > 0 => Payload::Hex(value.to_vec()),
+            (PayloadType::Flag, 0) => Payload::Flag,
+            (PayloadType::Enum(1, name), 1) => Payload::Enum(value[0] as u32, name(value[0] as u32)),
+            (PayloadType::Enum(2, name), 2) => {
+                let n = NativeEndian::read_u16(value) as u32;
+                Payload::Enum(n, name(n))
+            }
+            (PayloadType::Enum(4, name), 4) => {
+                let n = NativeEndian::read_u32(value);
+                Payload::Enum(n, name(n))
+            }
+            (PayloadType::BeEnum(2, name), 2) => {
+                let n = BigEndian::read_u16(value) as u32;
+                Payload::Enum(n, name(n))
+            }
+            (PayloadType::S32s(names), n) if n > 0 && n % 4 == 0 => {
+                Payload::S32s(value.chunks(4).map(NativeEndian::read_i32).collect(), names)
+            }
+            (PayloadType::U16s, n) if n > 0 && n % 2 == 0 => {
+                Payload::U16s(value.chunks(2).map(NativeEndian::read_u16).collect())
+            }
+            (PayloadType::U32s, n) if n > 0 && n % 4 == 0 => {
+                Payload::U32s(value.chunks(4).map(NativeEndian::read_u32).collect())
+            }
+            (PayloadType::U64s, n) if n > 0 && n % 8 == 0 => {
+                Payload::U64s(value.chunks(8).map(NativeEndian::read_u64).collect())
+            }
+            (PayloadType::Ip, 4) => {
+                Payload::Ip(IpAddr::V4(Ipv4Addr::new(value[0], value[1], value[2], value[3])))
+            }
+            (PayloadType::Ip, 16) => {
+                let mut a = [0u16; 8];
+                for i in 0..8 {
+                    a[i] = BigEndian::read_u16(&value[i*2..]);
+                }
+                Payload::Ip(IpAddr::V6(Ipv6Addr::new(a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7])))
+            }
+            (PayloadType::Nexthops, _) => Payload::Nexthops(try!(Rtnexthop::read_all(cursor, end, family))),
+            (PayloadType::Nested(read), _) => Payload::Nested(try!(read(cursor, end, family))),
+            (PayloadType::Struct(read), _) => Payload::Struct(try!(read(cursor, end))),
+            _ => Payload::Bytes,
+        };
+        Ok(p)
+    }
+}
+
+/// An enum that names attributes. Its [attr-types] table, if it has one,
+/// says what the payload of each attribute is.
+pub trait AttrType: Sized + Default + fmt::Display + FromPrimitive {
+    fn payload_type(_: &Value<Self>) -> PayloadType {
+        PayloadType::Bytes
+    }
+    /// Like payload_type(), for attributes whose payload depends on the
+    /// other attributes or the address family of the message. read_attrs()
+    /// asks with the attributes before this one, then if that wasn't
+    /// enough, all of them.
+    fn payload_type_after(rta_type: &Value<Self>, _: &[Rtattr<Self>],
+                          _: Value<AddressFamily>) -> PayloadType {
+        Self::payload_type(rta_type)
+    }
+}
+impl AttrType for u16 {}
+
+/// Read attributes until `end`. `family` is the address family of the
+/// message that they are in.
+pub fn read_attrs<T: AttrType>(cursor: &mut Cursor<&[u8]>, end: usize,
+                               family: Value<AddressFamily>) -> error::Result<Vec<Rtattr<T>>> {
+    let mut v = Vec::new();
+    try!(read_attrs_into(cursor, end, family, &mut v));
+    Ok(v)
+}
+
+/// Like read_attrs(), but the attributes are pushed onto `v`, so what was
+/// read before an error is left there
+pub fn read_attrs_into<T: AttrType>(cursor: &mut Cursor<&[u8]>, end: usize,
+                                    family: Value<AddressFamily>,
+                                    v: &mut Vec<Rtattr<T>>) -> error::Result<()> {
+    let mut starts = Vec::new();
+    while (cursor.position() as usize) < end {
+        starts.push(cursor.position());
+        let a = try!(Rtattr::read_after(cursor, end, family, v));
+        v.push(a);
+    }
+    // some payloads depend on an attribute that comes after them, eg. the
+    // kernel puts RTA_ENCAP before RTA_ENCAP_TYPE, so have another go at
+    // the ones that we couldn't decode with all of them
+    let after = cursor.position();
+    for i in 0..v.len() {
+        match v[i].rta_payload {
+            Payload::Bytes | Payload::Nested(Nested::Raw(_)) => (),
+            _ => continue,
+        }
+        let p = match T::payload_type_after(&v[i].rta_type, v, family) {
+            PayloadType::Bytes => continue,
+            t => {
+                // the payload is after the 4 byte rtattr header
+                cursor.set_position(starts[i] + 4);
+                try!(Payload::read(t, cursor, &v[i].rta_value, v[i].is_net_byteorder(), family))
+            }
+        };
+        v[i].rta_payload = p;
+    }
+    cursor.set_position(after);
+    Ok(())
+}
+
+/// The string in the attribute of type `t`, or the name of the number in
+/// it, if there is one
+fn string_attr<T: PartialEq>(attrs: &[Rtattr<T>], t: T) -> Option<&str> {
+    let t = Value::Known(t);
+    attrs.iter().find(|a| a.rta_type == t).and_then(|a| match a.rta_payload {
+        Payload::String(ref s) => Some(&s[..]),
+        Payload::Enum(_, ref name) => Some(&name[..]),
+        _ => None,
+    })
+}
+
+/// Numbers on one line, [ 1, 2, 3 ]
+fn fmt_numbers<T: fmt::Display>(f: &mut fmt::Formatter, v: &[T]) -> fmt::Result {
+    try!(write!(f, "["));
+    for (i, n) in v.iter().enumerate() {
+        try!(write!(f, "{} {}", if i == 0 { "" } else { "," }, n));
+    }
+    write!(f, " ]")
+}
+
+/// A u32 of flags, printed with the names that T has for them
+struct Flags<T> {
+    flags: u32,
+    names: PhantomData<T>,
+}
+impl<T> Flags<T> {
+    fn new(flags: u32) -> Flags<T> {
+        Flags { flags: flags, names: PhantomData }
+    }
+}
+impl<T: FromPrimitive + fmt::Display> fmt::Display for Flags<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_flags::<T>(f, self.flags)
+    }
+}
+
+fn fmt_attrs<T: AttrType>(f: &mut fmt::Formatter, attrs: &[Rtattr<T>], indent: i32) -> fmt::Result {
+    let i_s_p = format_indent(indent+1);
+    try!(write!(f, "[ "));
+    let mut count: usize = 1;
+    for a in attrs.iter() {
+        try!(a.pretty_fmt(f, indent+1));
+        if count < attrs.len() {
+            try!(write!(f, ",\n{}", i_s_p));
+        }
+        count = count + 1;
+    }
+    write!(f, " ]")
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Rtattr<T> {
      // the length originally encoded in the netlink which includes rta_len,
      // rta_type, and rta_value, but not any padding
-    rta_len: u16,
-    rta_type: T,
-    rta_value: Vec<u8>,
+    pub rta_len: u16,
+    pub rta_type: Value<T>,
+    pub rta_flags: u16, // NLA_F_NESTED and NLA_F_NET_BYTEORDER, taken off rta_type
+    pub rta_value: Vec<u8>,
+    pub rta_payload: Payload, // rta_value decoded, if we know how
 }
-impl <T: Default + ::std::fmt::Display + ::num::traits::FromPrimitive> Rtattr<T> {
-    // Ifinfomsg header is native endian
-    pub fn read(cursor: &mut Cursor<&[u8]>) -> io::Result<Rtattr<T>> {
+impl <T: AttrType> Rtattr<T> {
+    // Rtattr header is native endian
+    /// Read one attribute that has to fit before `end`, the end of the
+    /// message or attribute that it is in. If it has attributes inside it
+    /// they are read too.
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize,
+                family: Value<AddressFamily>) -> error::Result<Rtattr<T>> {
+        Rtattr::read_after(cursor, end, family, &[])
+    }
+    /// Like read(), when `prev` are the attributes before this one
+    pub fn read_after(cursor: &mut Cursor<&[u8]>, end: usize, family: Value<AddressFamily>,
+                      prev: &[Rtattr<T>]) -> error::Result<Rtattr<T>> {
         let mut s = Rtattr::default();
-        s.rta_len = try!(cursor.read_u16::<NativeEndian>());
+        let start = cursor.position() as usize;
+        s.rta_len = try!(read_u16::<NativeEndian>(cursor, end, "rtattr"));
         let rta_type_u16: u16;
-        rta_type_u16 = try!(cursor.read_u16::<NativeEndian>());
-        let rta_type = T::from_u16(rta_type_u16);
-        if rta_type.is_none() {
-            let e = io::Error::new(io::ErrorKind::Other, format!("rta_type = {}", rta_type_u16));
-            return Err(e);
-        }
-        s.rta_type = rta_type.unwrap();
-        if s.rta_len < 4 {
-            let e = io::Error::new(io::ErrorKind::Other, format!("rta_len = {}", s.rta_len));
-            return Err(e);
-        }
+        rta_type_u16 = try!(read_u16::<NativeEndian>(cursor, end, "rtattr"));
         // sizeof(rta_len) + sizeof(rta_type) = 4
-        let payload_len: usize = (s.rta_len - 4) as usize;
-        let mut vec: Vec<u8> = Vec::with_capacity(payload_len);
-        for _ in 0..payload_len {
-            let a = try!(cursor.read_u8());
-            vec.push(a);
+        if s.rta_len < 4 || start + s.rta_len as usize > end {
+            return Err(Error::new(ErrorKind::BadRtaLen { rta_len: s.rta_len,
+                                                         available: end - start },
+                                  start));
         }
-        s.rta_value = vec;
+        s.rta_type = Value::new((rta_type_u16 & NLA_TYPE_MASK) as u32);
+        s.rta_flags = rta_type_u16 & !NLA_TYPE_MASK;
+        let payload_len: usize = (s.rta_len - 4) as usize;
+        s.rta_value = try!(read_bytes(cursor, end, payload_len, "rta_value"));
+        let attr_end = start + s.rta_len as usize;
+        cursor.set_position((start + 4) as u64);
+        let payload_type = match T::payload_type_after(&s.rta_type, prev, family) {
+            PayloadType::Bytes if s.is_nested() => PayloadType::Nested(read_nested_raw),
+            t => t,
+        };
+        s.rta_payload = try!(Payload::read(payload_type, cursor, &s.rta_value,
+                                           s.is_net_byteorder(), family));
+        cursor.set_position(attr_end as u64);
         NlMsg::nlmsg_align(cursor);
         return Ok(s)
     }
+    /// Was NLA_F_NESTED set?
+    pub fn is_nested(&self) -> bool {
+        self.rta_flags & NLA_F_NESTED != 0
+    }
+    /// Was NLA_F_NET_BYTEORDER set?
+    pub fn is_net_byteorder(&self) -> bool {
+        self.rta_flags & NLA_F_NET_BYTEORDER != 0
+    }
     pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
-        let indent = format_indent(indent);
+        let i_s = format_indent(indent);
         try!(write!(f, "{{\n"));
-        try!(write!(f, "{}    rta_len: {},\n", indent, self.rta_len));
-        try!(write!(f, "{}    rta_type: {},\n", indent, self.rta_type));
-        try!(write!(f, "{}    rta_value: [", indent));
-        let mut count: usize = 1;
-        for a in self.rta_value.iter() {
-            try!(write!(f, " {:#X}", a));
-            if count < self.rta_value.len() {
-                try!(write!(f, ","));
+        try!(write!(f, "{}    rta_len: {},\n", i_s, self.rta_len));
+        try!(write!(f, "{}    rta_type: {}", i_s, self.rta_type));
+        if self.is_nested() {
+            try!(write!(f, " | NLA_F_NESTED"));
+        }
+        if self.is_net_byteorder() {
+            try!(write!(f, " | NLA_F_NET_BYTEORDER"));
+        }
+        try!(write!(f, ",\n{}    rta_value: ", i_s));
+        match self.rta_payload {
+            Payload::Bytes => {
+                try!(write!(f, "["));
+                let mut count: usize = 1;
+                for a in self.rta_value.iter() {
+                    try!(write!(f, " {:#X}", a));
+                    if count < self.rta_value.len() {
+                        try!(write!(f, ","));
+                    }
+                    count = count + 1;
+                }
+                try!(write!(f, " ]"));
             }
-            count = count + 1;
+            Payload::U8(n) => try!(write!(f, "{}", n)),
+            Payload::U16(n) => try!(write!(f, "{}", n)),
+            Payload::U32(n) => try!(write!(f, "{}", n)),
+            Payload::U64(n) => try!(write!(f, "{}", n)),
+            Payload::S32(n) => try!(write!(f, "{}", n)),
+            Payload::String(ref s) => try!(write!(f, "{:?}", s)),
+            Payload::Mac(ref m) => {
+                for (i, b) in m.iter().enumerate() {
+                    try!(write!(f, "{}{:02x}", if i == 0 { "" } else { ":" }, b));
+                }
+            }
+            Payload::Ip(ref ip) => try!(write!(f, "{}", ip)),
+            Payload::Prefix(ref ip, len) => try!(write!(f, "{}/{}", ip, len)),
+            Payload::Mpls(ref v) => {
+                try!(write!(f, "["));
+                for (i, lse) in v.iter().enumerate() {
+                    // label:20 tc:3 s:1 ttl:8
+                    try!(write!(f, "{} label {} tc {} s {} ttl {}", if i == 0 { "" } else { "," },
+                                lse >> 12, (lse >> 9) & 7, (lse >> 8) & 1, lse & 0xFF));
+                }
+                try!(write!(f, " ]"));
+            }
+            Payload::Handle(h) => try!(write!(f, "{}", h)),
+            Payload::Hex(ref h) => {
+                try!(write!(f, "0x"));
+                for b in h.iter() {
+                    try!(write!(f, "{:02x}", b));
+                }
+            }
+            Payload::Flag => try!(write!(f, "true")),
+            Payload::Enum(_, ref name) => try!(write!(f, "{}", name)),
+            Payload::S32s(ref v, names) => {
+                // one to a line, some of the sysctl names are long
+                let i_s_p = format_indent(indent+1);
+                try!(write!(f, "{{\n"));
+                for (i, n) in v.iter().enumerate() {
+                    match names.get(i) {
+                        Some(name) => try!(write!(f, "{}    {}: {},\n", i_s_p, name, n)),
+                        None => try!(write!(f, "{}    [{}]: {},\n", i_s_p, i, n)),
+                    }
+                }
+                try!(write!(f, "{}}}", i_s_p));
+            }
+            Payload::U16s(ref v) => try!(fmt_numbers(f, v)),
+            Payload::U32s(ref v) => try!(fmt_numbers(f, v)),
+            Payload::U64s(ref v) => try!(fmt_numbers(f, v)),
+            Payload::Nexthops(ref v) => {
+                // lined up the way fmt_attrs does nested attributes
+                let i_s_p = format_indent(indent+2);
+                try!(write!(f, "[ "));
+                for (i, nh) in v.iter().enumerate() {
+                    if i > 0 {
+                        try!(write!(f, ",\n{}", i_s_p));
+                    }
+                    try!(nh.pretty_fmt(f, indent+2));
+                }
+                try!(write!(f, " ]"));
+            }
+            Payload::Nested(ref n) => try!(n.pretty_fmt(f, indent+1)),
+            Payload::Struct(ref s) => try!(s.pretty_fmt(f, indent+1)),
         }
-        write!(f, " ],\n{}}}", indent)
+        write!(f, ",\n{}}}", i_s)
+    }
+}
+
+fn read_nested_raw(cursor: &mut Cursor<&[u8]>, end: usize,
+                   family: Value<AddressFamily>) -> error::Result<Nested> {
+    read_attrs(cursor, end, family).map(Nested::Raw)
+}
+
+/// Attributes that are numbered 1, 2, 3... by their order, each of which
+/// has more attributes that `read` reads, like the actions in TCA_FLOWER_ACT
+fn read_list(cursor: &mut Cursor<&[u8]>, end: usize, family: Value<AddressFamily>,
+             read: fn(&mut Cursor<&[u8]>, usize, Value<AddressFamily>) -> error::Result<Nested>)
+             -> error::Result<Vec<Rtattr<u16>>> {
+    let mut v = Vec::new();
+    while (cursor.position() as usize) < end {
+        let start = cursor.position();
+        let mut a = try!(Rtattr::<u16>::read(cursor, end, family));
+        let after = cursor.position();
+        cursor.set_position(start + 4);
+        a.rta_payload = try!(Payload::read(PayloadType::Nested(read), cursor, &a.rta_value,
+                                           false, family));
+        cursor.set_position(after);
+        v.push(a);
     }
+    Ok(v)
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct Ifinfomsg {
-    pub ifi_family: AddressFamily, // AF_UNSPEC
+    pub ifi_family: Value<AddressFamily>, // AF_UNSPEC
     pub ifi_type: u16,  // Device type
     pub ifi_index: i32, // Interface index
     pub ifi_flags: u32, // Device flags
@@ -113,32 +662,45 @@ pub struct Ifinfomsg {
     pub ifi_attr: Vec<Rtattr<Ifla>>,
 }
 impl Ifinfomsg {
-    // Ifinfomsg header is native endian
-    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> io::Result<Ifinfomsg> {
+    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<Ifinfomsg> {
         let mut s = Ifinfomsg::default();
-
+        try!(s.read_into(cursor, nlmsg_len));
+        Ok(s)
+    }
+    // Ifinfomsg header is native endian
+    /// Like read(), but what was decoded before an error is left in self
+    pub fn read_into(&mut self, cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<()> {
+        try!(check_len(cursor, nlmsg_len, 16, "ifinfomsg"));
         let family_u8: u8;
-        family_u8 = try!(cursor.read_u8());
-        let family = AddressFamily::from_u8(family_u8);
-        if family.is_none() {
-            let e = io::Error::new(io::ErrorKind::Other, format!("family_u8 = {}", family_u8));
-            return Err(e);
-        }
-        s.ifi_family = family.unwrap();
+        family_u8 = try!(read_u8(cursor, nlmsg_len, "ifinfomsg"));
         let mut _ifi_pad: u8 = 0;
-        _ifi_pad = try!(cursor.read_u8());
-        s.ifi_type = try!(cursor.read_u16::<NativeEndian>());
-        s.ifi_index = try!(cursor.read_i32::<NativeEndian>());
-        s.ifi_flags = try!(cursor.read_u32::<NativeEndian>());
-        s.ifi_change = try!(cursor.read_u32::<NativeEndian>());
-        while (cursor.position() as usize) < nlmsg_len {
-            let attr = try!(Rtattr::<Ifla>::read(cursor));
-            s.ifi_attr.push(attr);
-        }
+        _ifi_pad = try!(read_u8(cursor, nlmsg_len, "ifinfomsg"));
+        self.ifi_type = try!(read_u16::<NativeEndian>(cursor, nlmsg_len, "ifinfomsg"));
+        self.ifi_index = try!(read_i32::<NativeEndian>(cursor, nlmsg_len, "ifinfomsg"));
+        self.ifi_flags = try!(read_u32::<NativeEndian>(cursor, nlmsg_len, "ifinfomsg"));
+        self.ifi_change = try!(read_u32::<NativeEndian>(cursor, nlmsg_len, "ifinfomsg"));
+        self.ifi_family = Value::new(family_u8 as u32);
+        try!(read_attrs_into(cursor, nlmsg_len, self.ifi_family, &mut self.ifi_attr));
 
-        Ok(s)
+        Ok(())
     }
-    pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+    /// The interface counters, from IFLA_STATS64 or, if there isn't one,
+    /// IFLA_STATS, and how many bits wide they are, 64 or 32
+    pub fn stats(&self) -> Option<(Vec<(&'static str, u64)>, u32)> {
+        let find = |t: Ifla| self.ifi_attr.iter().find(|a| a.rta_type == Value::Known(t));
+        if let Some(a) = find(Ifla::IFLA_STATS64) {
+            let mut cursor = Cursor::new(&a.rta_value[..]);
+            return RtnlLinkStats64::read(&mut cursor, a.rta_value.len()).ok()
+                                                                        .map(|s| (s.counters(), 64));
+        }
+        find(Ifla::IFLA_STATS).and_then(|a| {
+            let mut cursor = Cursor::new(&a.rta_value[..]);
+            RtnlLinkStats::read(&mut cursor, a.rta_value.len()).ok().map(|s| (s.counters(), 32))
+        })
+    }
+}
+impl Pretty for Ifinfomsg {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
         let i_s = format_indent(indent);
         let i_s_p = format_indent(indent+1);
         try!(write!(f, "{{\n"));
@@ -146,7 +708,7 @@ impl Ifinfomsg {
         try!(write!(f, "{}    ifi_type: {},\n", i_s, self.ifi_type));
         try!(write!(f, "{}    ifi_index: {},\n", i_s, self.ifi_index));
         try!(write!(f, "{}    ifi_flags: {:#X} (", i_s, self.ifi_flags));
-        try!(NetDeviceFlags::pretty_fmt(f, self.ifi_flags));
+        try!(fmt_flags::<NetDeviceFlags>(f, self.ifi_flags));
         try!(write!(f, "),\n{}    ifi_change: {},\n", i_s, self.ifi_change));
         try!(write!(f, "{}    ifi_attr: [ ", i_s));
 
@@ -161,6 +723,12 @@ impl Ifinfomsg {
         write!(f, " ],\n{}}}", i_s)
     }
 }
+// VETH_INFO_PEER is a whole ifinfomsg, attributes and all
+impl AttrStruct for Ifinfomsg {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
 impl ::std::fmt::Display for Ifinfomsg {
     #[allow(dead_code)]
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
@@ -170,7 +738,7 @@ impl ::std::fmt::Display for Ifinfomsg {
 
 #[derive(Debug, Default, Clone)]
 pub struct Ifaddrmsg {
-    pub ifa_family: AddressFamily, // Address type
+    pub ifa_family: Value<AddressFamily>, // Address type
     pub ifa_prefixlen: u8, // Prefixlength of address
     pub ifa_flags: u8, // Address flags
     pub ifa_scope: u8, // Address scope
@@ -178,28 +746,45 @@ pub struct Ifaddrmsg {
     pub ifa_attr: Vec<Rtattr<Ifa>>,
 }
 impl Ifaddrmsg {
-    // Ifaddrmsg header is native endian
-    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> io::Result<Ifaddrmsg> {
+    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<Ifaddrmsg> {
         let mut s = Ifaddrmsg::default();
-
+        try!(s.read_into(cursor, nlmsg_len));
+        Ok(s)
+    }
+    // Ifaddrmsg header is native endian
+    /// Like read(), but what was decoded before an error is left in self
+    pub fn read_into(&mut self, cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<()> {
+        try!(check_len(cursor, nlmsg_len, 8, "ifaddrmsg"));
         let family_u8: u8;
-        family_u8 = try!(cursor.read_u8());
-        let family = AddressFamily::from_u8(family_u8);
-        if family.is_none() {
-            let e = io::Error::new(io::ErrorKind::Other, format!("family_u8 = {}", family_u8));
-            return Err(e);
-        }
-        s.ifa_family = family.unwrap();
-        s.ifa_prefixlen = try!(cursor.read_u8());
-        s.ifa_flags = try!(cursor.read_u8());
-        s.ifa_scope = try!(cursor.read_u8());
-        s.ifa_index = try!(cursor.read_u32::<NativeEndian>());
-        while (cursor.position() as usize) < nlmsg_len {
-            let attr = try!(Rtattr::<Ifa>::read(cursor));
-            s.ifa_attr.push(attr);
+        family_u8 = try!(read_u8(cursor, nlmsg_len, "ifaddrmsg"));
+        self.ifa_prefixlen = try!(read_u8(cursor, nlmsg_len, "ifaddrmsg"));
+        self.ifa_flags = try!(read_u8(cursor, nlmsg_len, "ifaddrmsg"));
+        self.ifa_scope = try!(read_u8(cursor, nlmsg_len, "ifaddrmsg"));
+        self.ifa_index = try!(read_u32::<NativeEndian>(cursor, nlmsg_len, "ifaddrmsg"));
+        self.ifa_family = Value::new(family_u8 as u32);
+        try!(read_attrs_into(cursor, nlmsg_len, self.ifa_family, &mut self.ifa_attr));
+        // the addresses are printed in CIDR form, with ifa_prefixlen
+        for attr in self.ifa_attr.iter_mut() {
+            let is_addr = attr.rta_type == Value::Known(Ifa::IFA_ADDRESS) ||
+                          attr.rta_type == Value::Known(Ifa::IFA_LOCAL);
+            match attr.rta_payload {
+                Payload::Ip(ip) if is_addr => attr.rta_payload = Payload::Prefix(ip, self.ifa_prefixlen),
+                _ => (),
+            }
         }
 
-        Ok(s)
+        Ok(())
+    }
+    /// The address flags. IFA_FLAGS, if there is one, has all 32 bits of
+    /// them and the kernel ignores ifa_flags.
+    pub fn flags(&self) -> u32 {
+        let t = Value::Known(Ifa::IFA_FLAGS);
+        for a in self.ifa_attr.iter().filter(|a| a.rta_type == t) {
+            if let Payload::Enum(n, _) = a.rta_payload {
+                return n;
+            }
+        }
+        self.ifa_flags as u32
     }
     pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
         let i_s = format_indent(indent);
@@ -207,9 +792,11 @@ impl Ifaddrmsg {
         try!(write!(f, "{{\n"));
         try!(write!(f, "{}    ifa_family: {},\n", i_s, self.ifa_family));
         try!(write!(f, "{}    ifa_prefixlen: {},\n", i_s, self.ifa_prefixlen));
-        try!(write!(f, "{}    ifa_flags: {:#X} (", i_s, self.ifa_flags));
-        try!(IfaFlags::pretty_fmt(f, self.ifa_flags as u32));
-        try!(write!(f, "),\n{}    ifa_scope: {},\n", i_s, self.ifa_scope));
+        try!(write!(f, "{}    ifa_flags: {:#X} (", i_s, self.flags()));
+        try!(fmt_flags::<IfaFlags>(f, self.flags()));
+        try!(write!(f, "),\n{}    ifa_scope: {} (", i_s, self.ifa_scope));
+        try!(RtScope::pretty_fmt(f, self.ifa_scope));
+        try!(write!(f, "),\n"));
         try!(write!(f, "{}    ifa_index: {},\n", i_s, self.ifa_index));
         try!(write!(f, "{}    ifa_attr: [ ", i_s));
 
@@ -231,6 +818,53 @@ impl ::std::fmt::Display for Ifaddrmsg {
     }
 }
 
+/// struct ifa_cacheinfo, for IFA_CACHEINFO
+#[derive(Debug, Default, Copy, Clone)]
+pub struct IfaCacheinfo {
+    pub ifa_prefered: u32, // preferred lifetime in seconds
+    pub ifa_valid: u32, // valid lifetime in seconds
+    pub cstamp: u32, // created timestamp, hundredths of seconds
+    pub tstamp: u32, // updated timestamp, hundredths of seconds
+}
+impl IfaCacheinfo {
+    // IfaCacheinfo is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<IfaCacheinfo> {
+        let mut s = IfaCacheinfo::default();
+
+        s.ifa_prefered = try!(read_u32::<NativeEndian>(cursor, end, "ifa_cacheinfo"));
+        s.ifa_valid = try!(read_u32::<NativeEndian>(cursor, end, "ifa_cacheinfo"));
+        s.cstamp = try!(read_u32::<NativeEndian>(cursor, end, "ifa_cacheinfo"));
+        s.tstamp = try!(read_u32::<NativeEndian>(cursor, end, "ifa_cacheinfo"));
+
+        Ok(s)
+    }
+}
+// INFINITY_LIFE_TIME is how the kernel says forever
+fn fmt_lifetime(f: &mut fmt::Formatter, secs: u32) -> fmt::Result {
+    match secs {
+        0xFFFFFFFF => write!(f, "forever"),
+        n => write!(f, "{}sec", n),
+    }
+}
+impl Pretty for IfaCacheinfo {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    ifa_prefered: ", indent));
+        try!(fmt_lifetime(f, self.ifa_prefered));
+        try!(write!(f, ",\n{}    ifa_valid: ", indent));
+        try!(fmt_lifetime(f, self.ifa_valid));
+        try!(write!(f, ",\n{}    cstamp: {},\n", indent, self.cstamp));
+        try!(write!(f, "{}    tstamp: {},\n", indent, self.tstamp));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for IfaCacheinfo {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
 impl RtScope {
     fn pretty_fmt(f: &mut ::std::fmt::Formatter, num: u8) -> ::std::fmt::Result {
         let option = RtScope::from_u8(num);
@@ -242,8 +876,8 @@ impl RtScope {
 }
 
 impl RtTable {
-    fn pretty_fmt(f: &mut ::std::fmt::Formatter, num: u8) -> ::std::fmt::Result {
-        let option = RtTable::from_u8(num);
+    fn pretty_fmt(f: &mut ::std::fmt::Formatter, num: u32) -> ::std::fmt::Result {
+        let option = RtTable::from_u32(num);
         match option {
             Some(e) => write!(f, "{}", e),
             None => write!(f, "user defined"),
@@ -253,65 +887,63 @@ impl RtTable {
 
 #[derive(Debug, Default, Clone)]
 pub struct Rtmsg {
-    pub rtm_family: AddressFamily, // Address family of route
+    pub rtm_family: Value<AddressFamily>, // Address family of route
     pub rtm_dst_len: u8, // Length of destination
     pub rtm_src_len: u8, // Length of source
     pub rtm_tos: u8, // TOS filter
 
     pub rtm_table: u8, // Routing table ID
-    pub rtm_protocol: Rtprot, // Routing protocol
+    pub rtm_protocol: Value<Rtprot>, // Routing protocol
     pub rtm_scope: u8,
-    pub rtm_type: Rtn,
+    pub rtm_type: Value<Rtn>,
 
     pub rtm_flags: u32,
     pub rtm_attr: Vec<Rtattr<RtmAttr>>,
 }
 impl Rtmsg {
-    // Ifaddrmsg header is native endian
-    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> io::Result<Rtmsg> {
+    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<Rtmsg> {
         let mut s = Rtmsg::default();
-
+        try!(s.read_into(cursor, nlmsg_len));
+        Ok(s)
+    }
+    // Rtmsg header is native endian
+    /// Like read(), but what was decoded before an error is left in self
+    pub fn read_into(&mut self, cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<()> {
+        try!(check_len(cursor, nlmsg_len, 12, "rtmsg"));
         let family_u8: u8;
-        family_u8 = try!(cursor.read_u8());
-        let family = AddressFamily::from_u8(family_u8);
-        if family.is_none() {
-            let e = io::Error::new(io::ErrorKind::Other, format!("family_u8 = {}", family_u8));
-            return Err(e);
-        }
-        s.rtm_family = family.unwrap();
-        s.rtm_dst_len = try!(cursor.read_u8());
-        s.rtm_src_len = try!(cursor.read_u8());
-        s.rtm_tos = try!(cursor.read_u8());
-
-        s.rtm_table = try!(cursor.read_u8());
+        family_u8 = try!(read_u8(cursor, nlmsg_len, "rtmsg"));
+        self.rtm_dst_len = try!(read_u8(cursor, nlmsg_len, "rtmsg"));
+        self.rtm_src_len = try!(read_u8(cursor, nlmsg_len, "rtmsg"));
+        self.rtm_tos = try!(read_u8(cursor, nlmsg_len, "rtmsg"));
+
+        self.rtm_table = try!(read_u8(cursor, nlmsg_len, "rtmsg"));
         let rtm_protocol_u8: u8;
-        rtm_protocol_u8 = try!(cursor.read_u8());
-        let rtm_protocol = Rtprot::from_u8(rtm_protocol_u8);
-        if rtm_protocol.is_none() {
-            let e = io::Error::new(io::ErrorKind::Other, format!("rtm_protocol = {}",
-                                                                 rtm_protocol_u8));
-            return Err(e);
-        }
-        s.rtm_protocol = rtm_protocol.unwrap();
-        s.rtm_scope = try!(cursor.read_u8());
+        rtm_protocol_u8 = try!(read_u8(cursor, nlmsg_len, "rtmsg"));
+        self.rtm_scope = try!(read_u8(cursor, nlmsg_len, "rtmsg"));
         let rtm_type_u8: u8;
-        rtm_type_u8 = try!(cursor.read_u8());
-        let rtm_type = Rtn::from_u8(rtm_type_u8);
-        if rtm_type.is_none() {
-            let e = io::Error::new(io::ErrorKind::Other, format!("rtm_type = {}",
-                                                                 rtm_type_u8));
-            return Err(e);
-        }
-        s.rtm_type = rtm_type.unwrap();
+        rtm_type_u8 = try!(read_u8(cursor, nlmsg_len, "rtmsg"));
+
+        self.rtm_flags = try!(read_u32::<NativeEndian>(cursor, nlmsg_len, "rtmsg"));
 
-        s.rtm_flags = try!(cursor.read_u32::<NativeEndian>());
+        self.rtm_family = Value::new(family_u8 as u32);
+        self.rtm_protocol = Value::new(rtm_protocol_u8 as u32);
+        self.rtm_type = Value::new(rtm_type_u8 as u32);
 
-        while (cursor.position() as usize) < nlmsg_len {
-            let attr = try!(Rtattr::<RtmAttr>::read(cursor));
-            s.rtm_attr.push(attr);
+        try!(read_attrs_into(cursor, nlmsg_len, self.rtm_family, &mut self.rtm_attr));
+        // RTA_DST and RTA_SRC are printed in CIDR form, with their lengths
+        for attr in self.rtm_attr.iter_mut() {
+            let len = match attr.rta_type {
+                Value::Known(RtmAttr::RTA_DST) => Some(self.rtm_dst_len),
+                Value::Known(RtmAttr::RTA_SRC) => Some(self.rtm_src_len),
+                _ => None,
+            };
+            match (&attr.rta_payload, len) {
+                (&Payload::Ip(ip), Some(len)) => attr.rta_payload = Payload::Prefix(ip, len),
+                _ => (),
+            }
         }
 
-        Ok(s)
+        Ok(())
     }
     pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
         let i_s = format_indent(indent);
@@ -321,15 +953,15 @@ impl Rtmsg {
         try!(write!(f, "{}    rtm_dst_len: {},\n", i_s, self.rtm_dst_len));
         try!(write!(f, "{}    rtm_src_len: {},\n", i_s, self.rtm_src_len));
         try!(write!(f, "{}    rtm_tos: {},\n", i_s, self.rtm_tos));
-        try!(write!(f, "{}    rtm_table: {} (", i_s, self.rtm_table));
-        try!(RtTable::pretty_fmt(f, self.rtm_table));
+        try!(write!(f, "{}    rtm_table: {} (", i_s, self.table()));
+        try!(RtTable::pretty_fmt(f, self.table()));
         try!(write!(f, "),\n{}    rtm_protocol: {},\n", i_s, self.rtm_protocol));
         try!(write!(f, "{}    rtm_scope: {} (", i_s, self.rtm_scope));
         try!(RtScope::pretty_fmt(f, self.rtm_scope));
         try!(write!(f, "),\n{}    rtm_type: {},\n", i_s, self.rtm_type));
 
         try!(write!(f, "{}    rtm_flags: {:#X} (", i_s, self.rtm_flags));
-        try!(RtmFlags::pretty_fmt(f, self.rtm_flags as u32));
+        try!(fmt_flags::<RtmFlags>(f, self.rtm_flags as u32));
         try!(write!(f, "),\n{}    rtm_attr: [ ", i_s));
         let mut count: usize = 1;
         for a in self.rtm_attr.iter() {
@@ -341,6 +973,27 @@ impl Rtmsg {
         }
         write!(f, " ],\n{}}}", i_s)
     }
+    /// The routing table. rtm_table only has room for 255, so tables above
+    /// that are in RTA_TABLE.
+    pub fn table(&self) -> u32 {
+        let t = Value::Known(RtmAttr::RTA_TABLE);
+        for a in self.rtm_attr.iter().filter(|a| a.rta_type == t) {
+            if let Payload::U32(n) = a.rta_payload {
+                return n;
+            }
+        }
+        self.rtm_table as u32
+    }
+    /// The output interface from RTA_OIF, if there is one
+    pub fn oif(&self) -> Option<i32> {
+        for a in self.rtm_attr.iter() {
+            if let Value::Known(RtmAttr::RTA_OIF) = a.rta_type {
+                let mut cursor = Cursor::new(&a.rta_value[..]);
+                return cursor.read_i32::<NativeEndian>().ok();
+            }
+        }
+        None
+    }
 }
 impl ::std::fmt::Display for Rtmsg {
     #[allow(dead_code)]
@@ -349,183 +1002,2644 @@ impl ::std::fmt::Display for Rtmsg {
     }
 }
 
+/// struct rtnexthop, one path of RTA_MULTIPATH
+#[derive(Debug, Default, Clone)]
+pub struct Rtnexthop {
+    pub rtnh_len: u16,
+    pub rtnh_flags: u8, // RTNH_F_*
+    pub rtnh_hops: u8, // the weight, less one
+    pub rtnh_ifindex: i32,
+    pub rtnh_attr: Vec<Rtattr<RtmAttr>>, // RTA_GATEWAY and the like
+}
+impl Rtnexthop {
+    // Rtnexthop is native endian
+    /// Read one rtnexthop and its attributes, which have to fit before `end`
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize,
+                family: Value<AddressFamily>) -> error::Result<Rtnexthop> {
+        let mut s = Rtnexthop::default();
+        let start = cursor.position() as usize;
+        s.rtnh_len = try!(read_u16::<NativeEndian>(cursor, end, "rtnexthop"));
+        s.rtnh_flags = try!(read_u8(cursor, end, "rtnexthop"));
+        s.rtnh_hops = try!(read_u8(cursor, end, "rtnexthop"));
+        s.rtnh_ifindex = try!(read_i32::<NativeEndian>(cursor, end, "rtnexthop"));
+        // sizeof(struct rtnexthop) = 8
+        if s.rtnh_len < 8 || start + s.rtnh_len as usize > end {
+            return Err(Error::new(ErrorKind::Truncated("rtnexthop"), start));
+        }
+        let nh_end = start + s.rtnh_len as usize;
+        s.rtnh_attr = try!(read_attrs(cursor, nh_end, family));
+        cursor.set_position(nh_end as u64);
+        NlMsg::nlmsg_align(cursor);
+        Ok(s)
+    }
+    /// Read rtnexthops until `end`
+    pub fn read_all(cursor: &mut Cursor<&[u8]>, end: usize,
+                    family: Value<AddressFamily>) -> error::Result<Vec<Rtnexthop>> {
+        let mut v = Vec::new();
+        while (cursor.position() as usize) < end {
+            v.push(try!(Rtnexthop::read(cursor, end, family)));
+        }
+        Ok(v)
+    }
+    pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    rtnh_len: {},\n", i_s, self.rtnh_len));
+        try!(write!(f, "{}    rtnh_flags: {:#X} (", i_s, self.rtnh_flags));
+        try!(fmt_flags::<RtnhFlags>(f, self.rtnh_flags as u32));
+        try!(write!(f, "),\n{}    rtnh_hops: {} (weight {}),\n", i_s, self.rtnh_hops,
+                    self.rtnh_hops as u32 + 1));
+        try!(write!(f, "{}    rtnh_ifindex: {},\n", i_s, self.rtnh_ifindex));
+        try!(write!(f, "{}    rtnh_attr: ", i_s));
+        try!(fmt_attrs(f, &self.rtnh_attr, indent));
+        write!(f, ",\n{}}}", i_s)
+    }
+}
+
+/// struct rta_cacheinfo, for RTA_CACHEINFO
 #[derive(Debug, Default, Copy, Clone)]
-pub struct NdaCacheinfo {
-    pub ndm_confirmed: u32,
-    pub ndm_used: u32,
-    pub ndm_updated: u32,
-    pub ndm_flags: u32,
+pub struct RtaCacheinfo {
+    pub rta_clntref: u32,
+    pub rta_lastuse: u32,
+    pub rta_expires: i32,
+    pub rta_error: u32,
+    pub rta_used: u32,
+    pub rta_id: u32,
+    pub rta_ts: u32,
+    pub rta_tsage: u32,
 }
-impl NdaCacheinfo {
-    // Ifinfomsg header is native endian
-    pub fn read(cursor: &mut Cursor<&[u8]>) -> io::Result<NdaCacheinfo> {
-        let mut s = NdaCacheinfo::default();
+impl RtaCacheinfo {
+    // RtaCacheinfo is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<RtaCacheinfo> {
+        let mut s = RtaCacheinfo::default();
 
-        s.ndm_confirmed = try!(cursor.read_u32::<NativeEndian>());
-        s.ndm_used = try!(cursor.read_u32::<NativeEndian>());
-        s.ndm_updated = try!(cursor.read_u32::<NativeEndian>());
-        s.ndm_flags = try!(cursor.read_u32::<NativeEndian>());
+        s.rta_clntref = try!(read_u32::<NativeEndian>(cursor, end, "rta_cacheinfo"));
+        s.rta_lastuse = try!(read_u32::<NativeEndian>(cursor, end, "rta_cacheinfo"));
+        s.rta_expires = try!(read_i32::<NativeEndian>(cursor, end, "rta_cacheinfo"));
+        s.rta_error = try!(read_u32::<NativeEndian>(cursor, end, "rta_cacheinfo"));
+        s.rta_used = try!(read_u32::<NativeEndian>(cursor, end, "rta_cacheinfo"));
+        s.rta_id = try!(read_u32::<NativeEndian>(cursor, end, "rta_cacheinfo"));
+        s.rta_ts = try!(read_u32::<NativeEndian>(cursor, end, "rta_cacheinfo"));
+        s.rta_tsage = try!(read_u32::<NativeEndian>(cursor, end, "rta_cacheinfo"));
 
         Ok(s)
     }
-    pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+}
+impl Pretty for RtaCacheinfo {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
         let indent = format_indent(indent);
         try!(write!(f, "{{\n"));
-        try!(write!(f, "{}    ndm_confirmed: {},\n", indent, self.ndm_confirmed));
-        try!(write!(f, "{}    ndm_used: {},\n", indent, self.ndm_used));
-        try!(write!(f, "{}    ndm_updated: {:#X},\n", indent, self.ndm_updated));
-        try!(write!(f, "{}    ndm_flags: {:#X},\n", indent, self.ndm_flags));
+        try!(write!(f, "{}    rta_clntref: {},\n", indent, self.rta_clntref));
+        try!(write!(f, "{}    rta_lastuse: {},\n", indent, self.rta_lastuse));
+        try!(write!(f, "{}    rta_expires: {},\n", indent, self.rta_expires));
+        try!(write!(f, "{}    rta_error: {},\n", indent, self.rta_error));
+        try!(write!(f, "{}    rta_used: {},\n", indent, self.rta_used));
+        try!(write!(f, "{}    rta_id: {},\n", indent, self.rta_id));
+        try!(write!(f, "{}    rta_ts: {},\n", indent, self.rta_ts));
+        try!(write!(f, "{}    rta_tsage: {},\n", indent, self.rta_tsage));
         write!(f, "{}}}", indent)
     }
 }
-impl ::std::fmt::Display for NdaCacheinfo {
-    #[allow(dead_code)]
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        self.pretty_fmt(f, 0)
+impl AttrStruct for RtaCacheinfo {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
     }
 }
 
+/// struct rtvia, for RTA_VIA: a gateway in another address family than the
+/// route, eg. an IPv6 nexthop for an IPv4 route
 #[derive(Debug, Default, Clone)]
-pub struct Ndmsg {
-    pub ndm_family: u8,
-    pub ndm_ifindex: i32, // Interface index
-    pub ndm_state: u16, // State
-    pub ndm_flags: u8, // Flags
-    // I *think* this is the right type, but don't have a lot of examples of
-    // its use.
-    pub ndm_type: NdAttr,
-    pub ndm_cacheinfo: Option<NdaCacheinfo>,
-    pub ndm_attr: Vec<Rtattr<NdAttr>>,
+pub struct RtVia {
+    pub rtvia_family: Value<AddressFamily>,
+    pub rtvia_addr: Vec<u8>,
 }
-impl Ndmsg {
-    // Ifinfomsg header is native endian
-    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> io::Result<Ndmsg> {
-        let mut s = Ndmsg::default();
+impl RtVia {
+    // RtVia is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<RtVia> {
+        let mut s = RtVia::default();
 
-        s.ndm_family = try!(cursor.read_u8());
-        let mut _ndm_pad_u8: u8 = 0;
-        _ndm_pad_u8 = try!(cursor.read_u8());
-        let mut _ndm_pad_u16: u16 = 0;
-        _ndm_pad_u16 = try!(cursor.read_u16::<NativeEndian>());
-        s.ndm_ifindex = try!(cursor.read_i32::<NativeEndian>());
-        s.ndm_state = try!(cursor.read_u16::<NativeEndian>());
-        s.ndm_flags = try!(cursor.read_u8());
-        let ndm_type_u8: u8;
-        ndm_type_u8 = try!(cursor.read_u8());
-        let ndm_type = NdAttr::from_u8(ndm_type_u8);
-        if ndm_type.is_none() {
-            let e = io::Error::new(io::ErrorKind::Other, format!("ndm_type = {}", ndm_type_u8));
-            return Err(e);
-        }
-        s.ndm_type = ndm_type.unwrap();
+        let family_u16 = try!(read_u16::<NativeEndian>(cursor, end, "rtvia"));
+        s.rtvia_family = Value::new(family_u16 as u32);
+        let len = end - cursor.position() as usize;
+        s.rtvia_addr = try!(read_bytes(cursor, end, len, "rtvia"));
 
-        if s.ndm_type == NdAttr::NDA_CACHEINFO {
-            let ndm_cacheinfo = try!(NdaCacheinfo::read(cursor));
-            s.ndm_cacheinfo = Some(ndm_cacheinfo);
+        Ok(s)
+    }
+}
+impl Pretty for RtVia {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, _: i32) -> fmt::Result {
+        let a = &self.rtvia_addr;
+        try!(write!(f, "{} ", self.rtvia_family));
+        match a.len() {
+            4 => write!(f, "{}", Ipv4Addr::new(a[0], a[1], a[2], a[3])),
+            16 => {
+                let mut w = [0u16; 8];
+                for i in 0..8 {
+                    w[i] = BigEndian::read_u16(&a[i*2..]);
+                }
+                write!(f, "{}", Ipv6Addr::new(w[0], w[1], w[2], w[3], w[4], w[5], w[6], w[7]))
+            }
+            _ => {
+                try!(write!(f, "0x"));
+                for b in a.iter() {
+                    try!(write!(f, "{:02x}", b));
+                }
+                Ok(())
+            }
         }
+    }
+}
+impl AttrStruct for RtVia {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
 
-        while (cursor.position() as usize) < nlmsg_len {
-            let attr = try!(Rtattr::<NdAttr>::read(cursor));
-            s.ndm_attr.push(attr);
-        }
+/// struct rtnl_link_ifmap, for IFLA_MAP
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RtnlLinkIfmap {
+    pub mem_start: u64,
+    pub mem_end: u64,
+    pub base_addr: u64,
+    pub irq: u16,
+    pub dma: u8,
+    pub port: u8,
+}
+impl RtnlLinkIfmap {
+    // RtnlLinkIfmap is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<RtnlLinkIfmap> {
+        let mut s = RtnlLinkIfmap::default();
+
+        s.mem_start = try!(read_u64::<NativeEndian>(cursor, end, "rtnl_link_ifmap"));
+        s.mem_end = try!(read_u64::<NativeEndian>(cursor, end, "rtnl_link_ifmap"));
+        s.base_addr = try!(read_u64::<NativeEndian>(cursor, end, "rtnl_link_ifmap"));
+        s.irq = try!(read_u16::<NativeEndian>(cursor, end, "rtnl_link_ifmap"));
+        s.dma = try!(read_u8(cursor, end, "rtnl_link_ifmap"));
+        s.port = try!(read_u8(cursor, end, "rtnl_link_ifmap"));
 
         Ok(s)
     }
-    pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
-        let i_s = format_indent(indent);
-        let i_s_p = format_indent(indent+1);
+}
+impl Pretty for RtnlLinkIfmap {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
         try!(write!(f, "{{\n"));
-        try!(write!(f, "{}    ndm_family: {},\n", i_s, self.ndm_family));
-        try!(write!(f, "{}    ndm_ifindex: {},\n", i_s, self.ndm_ifindex));
-        try!(write!(f, "{}    ndm_state: {:#X} (", i_s, self.ndm_state));
-        try!(NdState::pretty_fmt(f, self.ndm_state as u32));
-        try!(write!(f, "),\n{}    ndm_flags: {:#X} (", i_s, self.ndm_flags));
-        try!(NdFlags::pretty_fmt(f, self.ndm_flags as u32));
-        try!(write!(f, "),\n{}    ndm_type: {},\n", i_s, self.ndm_type));
-        try!(write!(f, "{}    ndm_cacheinfo: ", i_s));
-        match self.ndm_cacheinfo {
-            None => try!(write!(f, "None")),
-            Some(ref cacheinfo) => try!(cacheinfo.pretty_fmt(f, indent+1)),
-        }
-
-        // TODO: macro? Or move into Rtattr?
-        try!(write!(f, ",\n{}    ndm_attr: [ ", i_s));
-        let mut count: usize = 1;
-        for a in self.ndm_attr.iter() {
-            try!(a.pretty_fmt(f, indent+1));
-            if count < self.ndm_attr.len() {
-                try!(write!(f, ",\n{}", i_s_p));
-            }
-            count = count + 1;
-        }
-        write!(f, " ],\n{}}}", i_s)
+        try!(write!(f, "{}    mem_start: {:#X},\n", indent, self.mem_start));
+        try!(write!(f, "{}    mem_end: {:#X},\n", indent, self.mem_end));
+        try!(write!(f, "{}    base_addr: {:#X},\n", indent, self.base_addr));
+        try!(write!(f, "{}    irq: {},\n", indent, self.irq));
+        try!(write!(f, "{}    dma: {},\n", indent, self.dma));
+        try!(write!(f, "{}    port: {},\n", indent, self.port));
+        write!(f, "{}}}", indent)
     }
 }
-impl ::std::fmt::Display for Ndmsg {
-    #[allow(dead_code)]
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-        self.pretty_fmt(f, 0)
+impl AttrStruct for RtnlLinkIfmap {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
     }
 }
 
-#[derive(Debug, Default, Clone)]
-pub struct Tcmsg {
-    pub tcm_family: u8,
-    pub tcm_ifindex: i32,
-    pub tcm_handle: u32,
-    pub tcm_parent: u32,
-    pub tcm_info: u32,
-    pub tcm_attr: Vec<Rtattr<TcAttr>>,
+/// struct ifla_bridge_id, for IFLA_BR_ROOT_ID and the like
+#[derive(Debug, Default, Copy, Clone)]
+pub struct IflaBridgeId {
+    pub prio: u16,
+    pub addr: [u8; 6],
 }
-impl Tcmsg {
-    // Ifinfomsg header is native endian
-    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> io::Result<Tcmsg> {
-        let mut s = Tcmsg::default();
+impl IflaBridgeId {
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<IflaBridgeId> {
+        let mut s = IflaBridgeId::default();
 
-        s.tcm_family = try!(cursor.read_u8());
-        let mut _tcm_pad_u8: u8 = 0;
-        _tcm_pad_u8 = try!(cursor.read_u8());
-        let mut _tcm_pad_u16: u16 = 0;
-        _tcm_pad_u16 = try!(cursor.read_u16::<NativeEndian>());
-        s.tcm_ifindex = try!(cursor.read_i32::<NativeEndian>());
-        s.tcm_handle = try!(cursor.read_u32::<NativeEndian>());
-        s.tcm_parent = try!(cursor.read_u32::<NativeEndian>());
-        s.tcm_info = try!(cursor.read_u32::<NativeEndian>());
+        // prio is u8[2], so big endian
+        s.prio = try!(read_u16::<BigEndian>(cursor, end, "ifla_bridge_id"));
+        let addr = try!(read_bytes(cursor, end, 6, "ifla_bridge_id"));
+        s.addr.copy_from_slice(&addr);
 
-        // TODO: revisit. Move into Rtattr?
-        while (cursor.position() as usize) < nlmsg_len {
-            let attr = try!(Rtattr::<TcAttr>::read(cursor));
-            s.tcm_attr.push(attr);
-        }
+        Ok(s)
+    }
+}
+impl Pretty for IflaBridgeId {
+    // the way that brctl and ip print them, eg. 8000.02:00:00:00:00:01
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, _: i32) -> fmt::Result {
+        let a = &self.addr;
+        write!(f, "{:04x}.{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+               self.prio, a[0], a[1], a[2], a[3], a[4], a[5])
+    }
+}
+impl AttrStruct for IflaBridgeId {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// The sysctls in IFLA_INET_CONF, by index. Index 0 is IPV4_DEVCONF_FORWARDING,
+/// the kernel leaves off the 1 that the IPV4_DEVCONF_* enum starts at.
+pub const IPV4_DEVCONF: &'static [&'static str] = &[
+    "forwarding", "mc_forwarding", "proxy_arp", "accept_redirects",
+    "secure_redirects", "send_redirects", "shared_media", "rp_filter",
+    "accept_source_route", "bootp_relay", "log_martians", "tag", "arp_filter",
+    "medium_id", "disable_xfrm", "disable_policy", "force_igmp_version",
+    "arp_announce", "arp_ignore", "promote_secondaries", "arp_accept",
+    "arp_notify", "accept_local", "src_valid_mark", "proxy_arp_pvlan",
+    "route_localnet", "igmpv2_unsolicited_report_interval",
+    "igmpv3_unsolicited_report_interval", "ignore_routes_with_linkdown",
+    "drop_unicast_in_l2_multicast", "drop_gratuitous_arp", "bc_forwarding",
+    "arp_evict_nocarrier",
+];
+
+/// The sysctls in IFLA_INET6_CONF, by DEVCONF_* index
+pub const IPV6_DEVCONF: &'static [&'static str] = &[
+    "forwarding", "hop_limit", "mtu", "accept_ra", "accept_redirects",
+    "autoconf", "dad_transmits", "router_solicitations",
+    "router_solicitation_interval", "router_solicitation_delay",
+    "use_tempaddr", "temp_valid_lft", "temp_prefered_lft", "regen_max_retry",
+    "max_desync_factor", "max_addresses", "force_mld_version",
+    "accept_ra_defrtr", "accept_ra_pinfo", "accept_ra_rtr_pref",
+    "router_probe_interval", "accept_ra_rt_info_max_plen", "proxy_ndp",
+    "optimistic_dad", "accept_source_route", "mc_forwarding", "disable_ipv6",
+    "accept_dad", "force_tllao", "ndisc_notify",
+    "mldv1_unsolicited_report_interval", "mldv2_unsolicited_report_interval",
+    "suppress_frag_ndisc", "accept_ra_from_local", "use_optimistic",
+    "accept_ra_mtu", "stable_secret", "use_oif_addrs_only",
+    "accept_ra_min_hop_limit", "ignore_routes_with_linkdown",
+    "drop_unicast_in_l2_multicast", "drop_unsolicited_na", "keep_addr_on_down",
+    "router_solicitation_max_interval", "seg6_enabled", "seg6_require_hmac",
+    "enhanced_dad", "addr_gen_mode", "disable_policy",
+    "accept_ra_rt_info_min_plen", "ndisc_tclass", "rpl_seg_enabled",
+    "ra_defrtr_metric", "ioam6_enabled", "ioam6_id", "ioam6_id_wide",
+    "ndisc_evict_nocarrier", "accept_untracked_na", "accept_ra_min_lft",
+    "force_forwarding",
+];
+
+/// struct ifla_cacheinfo, for IFLA_INET6_CACHEINFO
+#[derive(Debug, Default, Copy, Clone)]
+pub struct IflaCacheinfo {
+    pub max_reasm_len: u32,
+    pub tstamp: u32, // ipv6InterfaceTable updated timestamp
+    pub reachable_time: u32,
+    pub retrans_time: u32,
+}
+impl IflaCacheinfo {
+    // IflaCacheinfo is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<IflaCacheinfo> {
+        let mut s = IflaCacheinfo::default();
+
+        s.max_reasm_len = try!(read_u32::<NativeEndian>(cursor, end, "ifla_cacheinfo"));
+        s.tstamp = try!(read_u32::<NativeEndian>(cursor, end, "ifla_cacheinfo"));
+        s.reachable_time = try!(read_u32::<NativeEndian>(cursor, end, "ifla_cacheinfo"));
+        s.retrans_time = try!(read_u32::<NativeEndian>(cursor, end, "ifla_cacheinfo"));
 
         Ok(s)
     }
-    pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
-        let i_s = format_indent(indent);
-        let i_s_p = format_indent(indent+1);
+}
+impl Pretty for IflaCacheinfo {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
         try!(write!(f, "{{\n"));
-        try!(write!(f, "{}    tcm_family: {},\n", i_s, self.tcm_family));
-        try!(write!(f, "{}    tcm_ifindex: {},\n", i_s, self.tcm_ifindex));
-        try!(write!(f, "{}    tcm_handle: {:#X},\n", i_s, self.tcm_handle));
-        try!(write!(f, "{}    tcm_parent: {:#X},\n", i_s, self.tcm_parent));
-        try!(write!(f, "{}    tcm_info: {},\n", i_s, self.tcm_info));
+        try!(write!(f, "{}    max_reasm_len: {},\n", indent, self.max_reasm_len));
+        try!(write!(f, "{}    tstamp: {},\n", indent, self.tstamp));
+        try!(write!(f, "{}    reachable_time: {},\n", indent, self.reachable_time));
+        try!(write!(f, "{}    retrans_time: {},\n", indent, self.retrans_time));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for IflaCacheinfo {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
 
-        // TODO: macro? Or move into Rtattr?
-        try!(write!(f, "{}    tcm_attr: [ ", i_s));
+/// struct bridge_vlan_info, for IFLA_BRIDGE_VLAN_INFO
+#[derive(Debug, Default, Copy, Clone)]
+pub struct BridgeVlanInfo {
+    pub flags: u16, // BRIDGE_VLAN_INFO_*
+    pub vid: u16,
+}
+impl BridgeVlanInfo {
+    // BridgeVlanInfo is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<BridgeVlanInfo> {
+        let mut s = BridgeVlanInfo::default();
+
+        s.flags = try!(read_u16::<NativeEndian>(cursor, end, "bridge_vlan_info"));
+        s.vid = try!(read_u16::<NativeEndian>(cursor, end, "bridge_vlan_info"));
+
+        Ok(s)
+    }
+}
+impl Pretty for BridgeVlanInfo {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    flags: {:#X} (", indent, self.flags));
+        try!(fmt_flags::<BridgeVlanInfoFlags>(f, self.flags as u32));
+        try!(write!(f, "),\n"));
+        try!(write!(f, "{}    vid: {},\n", indent, self.vid));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for BridgeVlanInfo {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct ipv6_sr_hdr, the segment routing header, for SEG6_LOCAL_SRH and
+/// in struct seg6_iptunnel_encap. TLVs after the segments are left out.
+#[derive(Debug, Default, Clone)]
+pub struct Ipv6SrHdr {
+    pub nexthdr: u8,
+    pub hdrlen: u8, // 8 byte units, not counting the first 8
+    pub sr_type: u8, // type in C, 4 for SRH
+    pub segments_left: u8,
+    pub first_segment: u8,
+    pub flags: u8,
+    pub tag: u16,
+    /// The last segment first, the way the header has them
+    pub segments: Vec<Ipv6Addr>,
+}
+impl Ipv6SrHdr {
+    // Ipv6SrHdr is big endian, it goes into packets as is
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<Ipv6SrHdr> {
+        let mut s = Ipv6SrHdr::default();
+
+        s.nexthdr = try!(read_u8(cursor, end, "ipv6_sr_hdr"));
+        s.hdrlen = try!(read_u8(cursor, end, "ipv6_sr_hdr"));
+        s.sr_type = try!(read_u8(cursor, end, "ipv6_sr_hdr"));
+        s.segments_left = try!(read_u8(cursor, end, "ipv6_sr_hdr"));
+        s.first_segment = try!(read_u8(cursor, end, "ipv6_sr_hdr"));
+        s.flags = try!(read_u8(cursor, end, "ipv6_sr_hdr"));
+        s.tag = try!(read_u16::<BigEndian>(cursor, end, "ipv6_sr_hdr"));
+        for _ in 0..s.first_segment as usize + 1 {
+            let b = try!(read_bytes(cursor, end, 16, "ipv6_sr_hdr"));
+            let mut a = [0u16; 8];
+            for i in 0..8 {
+                a[i] = BigEndian::read_u16(&b[i*2..]);
+            }
+            s.segments.push(Ipv6Addr::new(a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7]));
+        }
+
+        Ok(s)
+    }
+}
+impl Pretty for Ipv6SrHdr {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    nexthdr: {},\n", indent, self.nexthdr));
+        try!(write!(f, "{}    hdrlen: {},\n", indent, self.hdrlen));
+        try!(write!(f, "{}    type: {},\n", indent, self.sr_type));
+        try!(write!(f, "{}    segments_left: {},\n", indent, self.segments_left));
+        try!(write!(f, "{}    first_segment: {},\n", indent, self.first_segment));
+        try!(write!(f, "{}    flags: {:#X},\n", indent, self.flags));
+        try!(write!(f, "{}    tag: {},\n", indent, self.tag));
+        try!(write!(f, "{}    segments: [", indent));
+        for (i, a) in self.segments.iter().enumerate() {
+            try!(write!(f, "{} {}", if i == 0 { "" } else { "," }, a));
+        }
+        try!(write!(f, " ],\n"));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for Ipv6SrHdr {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct seg6_iptunnel_encap, for SEG6_IPTUNNEL_SRH
+#[derive(Debug, Default, Clone)]
+pub struct Seg6IptunnelEncap {
+    pub mode: Value<Seg6IptunMode>,
+    pub srh: Ipv6SrHdr,
+}
+impl Seg6IptunnelEncap {
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<Seg6IptunnelEncap> {
+        let mut s = Seg6IptunnelEncap::default();
+
+        // mode is a native endian int, the header after it is big endian
+        let mode = try!(read_i32::<NativeEndian>(cursor, end, "seg6_iptunnel_encap"));
+        s.mode = Value::new(mode as u32);
+        s.srh = try!(Ipv6SrHdr::read(cursor, end));
+
+        Ok(s)
+    }
+}
+impl Pretty for Seg6IptunnelEncap {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    mode: {},\n", i_s, self.mode));
+        try!(write!(f, "{}    srh: ", i_s));
+        try!(self.srh.pretty_fmt(f, indent+1));
+        write!(f, ",\n{}}}", i_s)
+    }
+}
+impl AttrStruct for Seg6IptunnelEncap {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+// A struct of counters that are all one type, like struct rtnl_link_stats and
+// rtnl_link_stats64, which are the same counters in u32s and u64s. Newer
+// kernels add counters on the end, so we stop at whatever the attribute has
+// room for.
+macro_rules! counters {
+    ($name:ident, $t:ty, $read:ident, $what:expr, $($field:ident),*) => {
+        #[derive(Debug, Default, Copy, Clone)]
+        pub struct $name {
+            $(pub $field: $t,)*
+        }
+        impl $name {
+            // $name is native endian
+            pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<$name> {
+                let mut s = $name::default();
+                $(
+                    if cursor.position() as usize >= end {
+                        return Ok(s);
+                    }
+                    s.$field = try!($read::<NativeEndian>(cursor, end, $what));
+                )*
+                Ok(s)
+            }
+            /// The names and values of the counters, in the order that the
+            /// kernel has them
+            pub fn counters(&self) -> Vec<(&'static str, u64)> {
+                vec![$((stringify!($field), self.$field as u64)),*]
+            }
+        }
+        impl Pretty for $name {
+            fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+                fmt_counters(f, &self.counters(), indent)
+            }
+        }
+        impl AttrStruct for $name {
+            fn box_clone(&self) -> Box<AttrStruct> {
+                Box::new(self.clone())
+            }
+        }
+        impl ::std::fmt::Display for $name {
+            #[allow(dead_code)]
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                self.pretty_fmt(f, 0)
+            }
+        }
+    }
+}
+counters!(RtnlLinkStats, u32, read_u32, "rtnl_link_stats",
+            rx_packets, tx_packets, rx_bytes, tx_bytes, rx_errors, tx_errors,
+            rx_dropped, tx_dropped, multicast, collisions,
+            rx_length_errors, rx_over_errors, rx_crc_errors, rx_frame_errors,
+            rx_fifo_errors, rx_missed_errors,
+            tx_aborted_errors, tx_carrier_errors, tx_fifo_errors,
+            tx_heartbeat_errors, tx_window_errors,
+            rx_compressed, tx_compressed, rx_nohandler);
+counters!(RtnlLinkStats64, u64, read_u64, "rtnl_link_stats64",
+            rx_packets, tx_packets, rx_bytes, tx_bytes, rx_errors, tx_errors,
+            rx_dropped, tx_dropped, multicast, collisions,
+            rx_length_errors, rx_over_errors, rx_crc_errors, rx_frame_errors,
+            rx_fifo_errors, rx_missed_errors,
+            tx_aborted_errors, tx_carrier_errors, tx_fifo_errors,
+            tx_heartbeat_errors, tx_window_errors,
+            rx_compressed, tx_compressed, rx_nohandler, rx_otherhost_dropped);
+
+/// Print counters as a table, two to a line, so that rx_* and tx_* line up
+fn fmt_counters(f: &mut fmt::Formatter, counters: &[(&'static str, u64)],
+                indent: i32) -> fmt::Result {
+    let indent = format_indent(indent);
+    try!(write!(f, "{{\n"));
+    for pair in counters.chunks(2) {
+        try!(write!(f, "{}   ", indent));
+        for &(name, n) in pair.iter() {
+            try!(write!(f, " {:<21}{:>12}", format!("{}:", name), n));
+        }
+        try!(write!(f, "\n"));
+    }
+    write!(f, "{}}}", indent)
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+pub struct NdaCacheinfo {
+    pub ndm_confirmed: u32,
+    pub ndm_used: u32,
+    pub ndm_updated: u32,
+    pub ndm_refcnt: u32,
+}
+impl NdaCacheinfo {
+    // NdaCacheinfo is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<NdaCacheinfo> {
+        let mut s = NdaCacheinfo::default();
+
+        s.ndm_confirmed = try!(read_u32::<NativeEndian>(cursor, end, "nda_cacheinfo"));
+        s.ndm_used = try!(read_u32::<NativeEndian>(cursor, end, "nda_cacheinfo"));
+        s.ndm_updated = try!(read_u32::<NativeEndian>(cursor, end, "nda_cacheinfo"));
+        s.ndm_refcnt = try!(read_u32::<NativeEndian>(cursor, end, "nda_cacheinfo"));
+
+        Ok(s)
+    }
+}
+impl Pretty for NdaCacheinfo {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    ndm_confirmed: {},\n", indent, self.ndm_confirmed));
+        try!(write!(f, "{}    ndm_used: {},\n", indent, self.ndm_used));
+        try!(write!(f, "{}    ndm_updated: {},\n", indent, self.ndm_updated));
+        try!(write!(f, "{}    ndm_refcnt: {},\n", indent, self.ndm_refcnt));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for NdaCacheinfo {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+impl ::std::fmt::Display for NdaCacheinfo {
+    #[allow(dead_code)]
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        self.pretty_fmt(f, 0)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Ndmsg {
+    pub ndm_family: Value<AddressFamily>, // AF_BRIDGE for FDB entries
+    pub ndm_ifindex: i32, // Interface index
+    pub ndm_state: u16, // State
+    pub ndm_flags: u8, // Flags
+    pub ndm_type: Value<Rtn>,
+    pub ndm_attr: Vec<Rtattr<NdAttr>>,
+}
+impl Ndmsg {
+    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<Ndmsg> {
+        let mut s = Ndmsg::default();
+        try!(s.read_into(cursor, nlmsg_len));
+        Ok(s)
+    }
+    // Ndmsg header is native endian
+    /// Like read(), but what was decoded before an error is left in self
+    pub fn read_into(&mut self, cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<()> {
+        try!(check_len(cursor, nlmsg_len, 12, "ndmsg"));
+        let family_u8: u8;
+        family_u8 = try!(read_u8(cursor, nlmsg_len, "ndmsg"));
+        let mut _ndm_pad_u8: u8 = 0;
+        _ndm_pad_u8 = try!(read_u8(cursor, nlmsg_len, "ndmsg"));
+        let mut _ndm_pad_u16: u16 = 0;
+        _ndm_pad_u16 = try!(read_u16::<NativeEndian>(cursor, nlmsg_len, "ndmsg"));
+        self.ndm_ifindex = try!(read_i32::<NativeEndian>(cursor, nlmsg_len, "ndmsg"));
+        self.ndm_state = try!(read_u16::<NativeEndian>(cursor, nlmsg_len, "ndmsg"));
+        self.ndm_flags = try!(read_u8(cursor, nlmsg_len, "ndmsg"));
+        let ndm_type_u8: u8;
+        ndm_type_u8 = try!(read_u8(cursor, nlmsg_len, "ndmsg"));
+        self.ndm_family = Value::new(family_u8 as u32);
+        self.ndm_type = Value::new(ndm_type_u8 as u32);
+
+        try!(read_attrs_into(cursor, nlmsg_len, self.ndm_family, &mut self.ndm_attr));
+
+        Ok(())
+    }
+    pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        let i_s_p = format_indent(indent+1);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    ndm_family: {},\n", i_s, self.ndm_family));
+        try!(write!(f, "{}    ndm_ifindex: {},\n", i_s, self.ndm_ifindex));
+        try!(write!(f, "{}    ndm_state: {:#X} (", i_s, self.ndm_state));
+        try!(fmt_flags::<NdState>(f, self.ndm_state as u32));
+        try!(write!(f, "),\n{}    ndm_flags: {:#X} (", i_s, self.ndm_flags));
+        try!(fmt_flags::<NdFlags>(f, self.ndm_flags as u32));
+        try!(write!(f, "),\n{}    ndm_type: {},\n", i_s, self.ndm_type));
+        if let Some(fdb) = self.fdb() {
+            try!(write!(f, "{}    fdb: {},\n", i_s, fdb));
+        }
+
+        // TODO: macro? Or move into Rtattr?
+        try!(write!(f, "{}    ndm_attr: [ ", i_s));
         let mut count: usize = 1;
-        for a in self.tcm_attr.iter() {
+        for a in self.ndm_attr.iter() {
             try!(a.pretty_fmt(f, indent+1));
-            if count < self.tcm_attr.len() {
+            if count < self.ndm_attr.len() {
                 try!(write!(f, ",\n{}", i_s_p));
             }
             count = count + 1;
         }
         write!(f, " ],\n{}}}", i_s)
     }
+    /// For an AF_BRIDGE FDB entry, the line that `bridge fdb show` prints
+    /// for it, with interface indexes instead of names
+    pub fn fdb(&self) -> Option<String> {
+        if self.ndm_family != Value::Known(AddressFamily::AF_BRIDGE) {
+            return None;
+        }
+        let mut v: Vec<String> = Vec::new();
+        let attr = |t: NdAttr| {
+            let t = Value::Known(t);
+            self.ndm_attr.iter().find(|a| a.rta_type == t).map(|a| &a.rta_payload)
+        };
+        if let Some(&Payload::Mac(ref m)) = attr(NdAttr::NDA_LLADDR) {
+            let m: Vec<String> = m.iter().map(|b| format!("{:02x}", b)).collect();
+            v.push(m.join(":"));
+        }
+        if self.ndm_ifindex != 0 {
+            v.push(format!("dev {}", self.ndm_ifindex));
+        }
+        if let Some(&Payload::Ip(ip)) = attr(NdAttr::NDA_DST) {
+            v.push(format!("dst {}", ip));
+        }
+        let numbers = [(NdAttr::NDA_VLAN, "vlan"), (NdAttr::NDA_PORT, "port"),
+                       (NdAttr::NDA_VNI, "vni"), (NdAttr::NDA_SRC_VNI, "src_vni"),
+                       (NdAttr::NDA_IFINDEX, "via"), (NdAttr::NDA_NH_ID, "nhid"),
+                       (NdAttr::NDA_LINK_NETNSID, "link-netnsid")];
+        for &(t, name) in numbers.iter() {
+            match attr(t) {
+                Some(&Payload::U16(n)) => v.push(format!("{} {}", name, n)),
+                Some(&Payload::U32(n)) => v.push(format!("{} {}", name, n)),
+                _ => (),
+            }
+        }
+        let flags = [(NdFlags::NTF_SELF as u32, "self"),
+                     (NdFlags::NTF_ROUTER as u32, "router"),
+                     (NdFlags::NTF_EXT_LEARNED as u32, "extern_learn"),
+                     (NdFlags::NTF_OFFLOADED as u32, "offload")];
+        for &(flag, name) in flags.iter() {
+            if self.ndm_flags as u32 & flag != 0 {
+                v.push(name.to_string());
+            }
+        }
+        if let Some(&Payload::U32(n)) = attr(NdAttr::NDA_MASTER) {
+            v.push(format!("master {}", n));
+        }
+        if self.ndm_flags as u32 & NdFlags::NTF_STICKY as u32 != 0 {
+            v.push("sticky".to_string());
+        }
+        let state = self.ndm_state as u32;
+        if state & NdState::NUD_PERMANENT as u32 != 0 {
+            v.push("permanent".to_string());
+        } else if state & NdState::NUD_NOARP as u32 != 0 {
+            v.push("static".to_string());
+        } else if state & NdState::NUD_STALE as u32 != 0 {
+            v.push("stale".to_string());
+        }
+        Some(v.join(" "))
+    }
 }
-impl ::std::fmt::Display for Tcmsg {
+impl ::std::fmt::Display for Ndmsg {
     #[allow(dead_code)]
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
         self.pretty_fmt(f, 0)
     }
 }
+
+/// A qdisc or class handle, major:minor in hex the way tc prints them, eg.
+/// 1:10. The special TC_H_* ones have their name after them.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct TcHandle(pub u32);
+impl TcHandle {
+    /// root for TC_H_ROOT, ingress for TC_H_INGRESS, which is also
+    /// TC_H_CLSACT, and ingress or egress for the TC_H_MIN_INGRESS and
+    /// TC_H_MIN_EGRESS under it
+    pub fn name(&self) -> Option<&'static str> {
+        self.name_for(None)
+    }
+    /// Like name(), for the parent of a qdisc of `kind`. TC_H_CLSACT is
+    /// TC_H_INGRESS, so it is only clsact under a clsact qdisc.
+    pub fn name_for(&self, kind: Option<&str>) -> Option<&'static str> {
+        match self.0 {
+            0xFFFFFFFF => Some("root"),
+            0xFFFFFFF1 if kind == Some("clsact") => Some("clsact"),
+            0xFFFFFFF1 | 0xFFFFFFF2 => Some("ingress"),
+            0xFFFFFFF3 => Some("egress"),
+            _ => None,
+        }
+    }
+    /// Print the handle with name_for(kind) after it
+    pub fn fmt_for(&self, f: &mut fmt::Formatter, kind: Option<&str>) -> fmt::Result {
+        try!(write!(f, "{:x}:{:x}", self.0 >> 16, self.0 & 0xFFFF));
+        match self.name_for(kind) {
+            Some(name) => write!(f, " ({})", name),
+            None => Ok(()),
+        }
+    }
+}
+impl fmt::Display for TcHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_for(f, None)
+    }
+}
+
+/// struct tc_stats, for TCA_STATS. The same counters are in TCA_STATS2.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcStats {
+    pub bytes: u64,
+    pub packets: u32,
+    pub drops: u32,
+    pub overlimits: u32,
+    pub bps: u32,
+    pub pps: u32,
+    pub qlen: u32,
+    pub backlog: u32,
+}
+impl TcStats {
+    // TcStats is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcStats> {
+        let mut s = TcStats::default();
+
+        s.bytes = try!(read_u64::<NativeEndian>(cursor, end, "tc_stats"));
+        s.packets = try!(read_u32::<NativeEndian>(cursor, end, "tc_stats"));
+        s.drops = try!(read_u32::<NativeEndian>(cursor, end, "tc_stats"));
+        s.overlimits = try!(read_u32::<NativeEndian>(cursor, end, "tc_stats"));
+        s.bps = try!(read_u32::<NativeEndian>(cursor, end, "tc_stats"));
+        s.pps = try!(read_u32::<NativeEndian>(cursor, end, "tc_stats"));
+        s.qlen = try!(read_u32::<NativeEndian>(cursor, end, "tc_stats"));
+        s.backlog = try!(read_u32::<NativeEndian>(cursor, end, "tc_stats"));
+
+        Ok(s)
+    }
+    pub fn counters(&self) -> Vec<(&'static str, u64)> {
+        vec![("bytes", self.bytes), ("packets", self.packets as u64),
+             ("drops", self.drops as u64), ("overlimits", self.overlimits as u64),
+             ("bps", self.bps as u64), ("pps", self.pps as u64),
+             ("qlen", self.qlen as u64), ("backlog", self.backlog as u64)]
+    }
+}
+impl Pretty for TcStats {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        fmt_counters(f, &self.counters(), indent)
+    }
+}
+impl AttrStruct for TcStats {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct gnet_stats_basic, for TCA_STATS_BASIC and TCA_STATS_BASIC_HW
+#[derive(Debug, Default, Copy, Clone)]
+pub struct GnetStatsBasic {
+    pub bytes: u64,
+    pub packets: u32,
+}
+impl GnetStatsBasic {
+    // GnetStatsBasic is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<GnetStatsBasic> {
+        let mut s = GnetStatsBasic::default();
+
+        s.bytes = try!(read_u64::<NativeEndian>(cursor, end, "gnet_stats_basic"));
+        s.packets = try!(read_u32::<NativeEndian>(cursor, end, "gnet_stats_basic"));
+
+        Ok(s)
+    }
+    pub fn counters(&self) -> Vec<(&'static str, u64)> {
+        vec![("bytes", self.bytes), ("packets", self.packets as u64)]
+    }
+}
+impl Pretty for GnetStatsBasic {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        fmt_counters(f, &self.counters(), indent)
+    }
+}
+impl AttrStruct for GnetStatsBasic {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+counters!(GnetStatsRateEst, u32, read_u32, "gnet_stats_rate_est", bps, pps);
+counters!(GnetStatsRateEst64, u64, read_u64, "gnet_stats_rate_est64", bps, pps);
+counters!(GnetStatsQueue, u32, read_u32, "gnet_stats_queue",
+          qlen, backlog, drops, requeues, overlimits);
+
+/// struct tc_sizespec, for TCA_STAB_BASE
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcSizespec {
+    pub cell_log: u8,
+    pub size_log: u8,
+    pub cell_align: i16,
+    pub overhead: i32,
+    pub linklayer: u32, // TC_LINKLAYER_*
+    pub mpu: u32,
+    pub mtu: u32,
+    pub tsize: u32, // how many u16 are in TCA_STAB_DATA
+}
+impl TcSizespec {
+    // TcSizespec is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcSizespec> {
+        let mut s = TcSizespec::default();
+
+        s.cell_log = try!(read_u8(cursor, end, "tc_sizespec"));
+        s.size_log = try!(read_u8(cursor, end, "tc_sizespec"));
+        s.cell_align = try!(read_u16::<NativeEndian>(cursor, end, "tc_sizespec")) as i16;
+        s.overhead = try!(read_i32::<NativeEndian>(cursor, end, "tc_sizespec"));
+        s.linklayer = try!(read_u32::<NativeEndian>(cursor, end, "tc_sizespec"));
+        s.mpu = try!(read_u32::<NativeEndian>(cursor, end, "tc_sizespec"));
+        s.mtu = try!(read_u32::<NativeEndian>(cursor, end, "tc_sizespec"));
+        s.tsize = try!(read_u32::<NativeEndian>(cursor, end, "tc_sizespec"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcSizespec {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    cell_log: {},\n", indent, self.cell_log));
+        try!(write!(f, "{}    size_log: {},\n", indent, self.size_log));
+        try!(write!(f, "{}    cell_align: {},\n", indent, self.cell_align));
+        try!(write!(f, "{}    overhead: {},\n", indent, self.overhead));
+        try!(write!(f, "{}    linklayer: {},\n", indent, self.linklayer));
+        try!(write!(f, "{}    mpu: {},\n", indent, self.mpu));
+        try!(write!(f, "{}    mtu: {},\n", indent, self.mtu));
+        try!(write!(f, "{}    tsize: {},\n", indent, self.tsize));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcSizespec {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_htb_xstats, the TCA_XSTATS of an htb class
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcHtbXstats {
+    pub lends: u32,
+    pub borrows: u32,
+    pub giants: u32, // unused since 2.6.25
+    pub tokens: i32,
+    pub ctokens: i32,
+}
+impl TcHtbXstats {
+    // TcHtbXstats is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcHtbXstats> {
+        let mut s = TcHtbXstats::default();
+
+        s.lends = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_xstats"));
+        s.borrows = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_xstats"));
+        s.giants = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_xstats"));
+        s.tokens = try!(read_i32::<NativeEndian>(cursor, end, "tc_htb_xstats"));
+        s.ctokens = try!(read_i32::<NativeEndian>(cursor, end, "tc_htb_xstats"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcHtbXstats {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    lends: {},\n", indent, self.lends));
+        try!(write!(f, "{}    borrows: {},\n", indent, self.borrows));
+        try!(write!(f, "{}    giants: {},\n", indent, self.giants));
+        try!(write!(f, "{}    tokens: {},\n", indent, self.tokens));
+        try!(write!(f, "{}    ctokens: {},\n", indent, self.ctokens));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcHtbXstats {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+counters!(TcFqCodelQdStats, u32, read_u32, "tc_fq_codel_qd_stats",
+          maxpacket, drop_overlimit, ecn_mark, new_flow_count, new_flows_len,
+          old_flows_len, ce_mark, memory_usage, drop_overmemory);
+
+/// struct tc_fq_codel_xstats, the TCA_XSTATS of fq_codel. The qdisc has
+/// counters, and each flow, which is a class, has where codel is at.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcFqCodelXstats {
+    pub xstats_type: u32, // type in C, TCA_FQ_CODEL_XSTATS_QDISC or _CLASS
+    pub qdisc_stats: TcFqCodelQdStats,
+    // struct tc_fq_codel_cl_stats
+    pub deficit: i32,
+    pub ldelay: u32, // in usecs
+    pub count: u32,
+    pub lastcount: u32,
+    pub dropping: u32,
+    pub drop_next: i32,
+}
+impl TcFqCodelXstats {
+    // TcFqCodelXstats is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcFqCodelXstats> {
+        let mut s = TcFqCodelXstats::default();
+
+        s.xstats_type = try!(read_u32::<NativeEndian>(cursor, end, "tc_fq_codel_xstats"));
+        if s.xstats_type == 0 {
+            s.qdisc_stats = try!(TcFqCodelQdStats::read(cursor, end));
+        } else {
+            s.deficit = try!(read_i32::<NativeEndian>(cursor, end, "tc_fq_codel_cl_stats"));
+            s.ldelay = try!(read_u32::<NativeEndian>(cursor, end, "tc_fq_codel_cl_stats"));
+            s.count = try!(read_u32::<NativeEndian>(cursor, end, "tc_fq_codel_cl_stats"));
+            s.lastcount = try!(read_u32::<NativeEndian>(cursor, end, "tc_fq_codel_cl_stats"));
+            s.dropping = try!(read_u32::<NativeEndian>(cursor, end, "tc_fq_codel_cl_stats"));
+            s.drop_next = try!(read_i32::<NativeEndian>(cursor, end, "tc_fq_codel_cl_stats"));
+        }
+
+        Ok(s)
+    }
+}
+impl Pretty for TcFqCodelXstats {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        if self.xstats_type == 0 {
+            return self.qdisc_stats.pretty_fmt(f, indent);
+        }
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    deficit: {},\n", indent, self.deficit));
+        try!(write!(f, "{}    ldelay: {},\n", indent, self.ldelay));
+        try!(write!(f, "{}    count: {},\n", indent, self.count));
+        try!(write!(f, "{}    lastcount: {},\n", indent, self.lastcount));
+        try!(write!(f, "{}    dropping: {},\n", indent, self.dropping));
+        try!(write!(f, "{}    drop_next: {},\n", indent, self.drop_next));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcFqCodelXstats {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_sfq_xstats, the TCA_XSTATS of an sfq flow
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcSfqXstats {
+    pub allot: i32,
+}
+impl TcSfqXstats {
+    // TcSfqXstats is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcSfqXstats> {
+        let mut s = TcSfqXstats::default();
+
+        s.allot = try!(read_i32::<NativeEndian>(cursor, end, "tc_sfq_xstats"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcSfqXstats {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    allot: {},\n", indent, self.allot));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcSfqXstats {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Tcmsg {
+    pub tcm_family: Value<AddressFamily>,
+    pub tcm_ifindex: i32,
+    pub tcm_handle: u32,
+    pub tcm_parent: u32,
+    pub tcm_info: u32,
+    pub tcm_attr: Vec<Rtattr<TcAttr>>,
+    /// From RTM_*TFILTER, where tcm_info is the prio and protocol
+    pub tcm_filter: bool,
+}
+impl Tcmsg {
+    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<Tcmsg> {
+        let mut s = Tcmsg::default();
+        try!(s.read_into(cursor, nlmsg_len));
+        Ok(s)
+    }
+    // Tcmsg header is native endian
+    /// Like read(), but what was decoded before an error is left in self
+    pub fn read_into(&mut self, cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<()> {
+        try!(check_len(cursor, nlmsg_len, 20, "tcmsg"));
+        let family_u8: u8;
+        family_u8 = try!(read_u8(cursor, nlmsg_len, "tcmsg"));
+        let mut _tcm_pad_u8: u8 = 0;
+        _tcm_pad_u8 = try!(read_u8(cursor, nlmsg_len, "tcmsg"));
+        let mut _tcm_pad_u16: u16 = 0;
+        _tcm_pad_u16 = try!(read_u16::<NativeEndian>(cursor, nlmsg_len, "tcmsg"));
+        self.tcm_ifindex = try!(read_i32::<NativeEndian>(cursor, nlmsg_len, "tcmsg"));
+        self.tcm_handle = try!(read_u32::<NativeEndian>(cursor, nlmsg_len, "tcmsg"));
+        self.tcm_parent = try!(read_u32::<NativeEndian>(cursor, nlmsg_len, "tcmsg"));
+        self.tcm_info = try!(read_u32::<NativeEndian>(cursor, nlmsg_len, "tcmsg"));
+
+        self.tcm_family = Value::new(family_u8 as u32);
+        let family = self.tcm_family;
+        try!(read_attrs_into(cursor, nlmsg_len, family, &mut self.tcm_attr));
+
+        // TCA_STATS_APP in TCA_STATS2 is the same as TCA_XSTATS, which is
+        // by TCA_KIND
+        let xstats = Value::Known(TcAttr::TCA_XSTATS);
+        let app = Value::Known(TcaStats::TCA_STATS_APP);
+        let t = TcAttr::payload_type_after(&xstats, &self.tcm_attr, family);
+        for a in self.tcm_attr.iter_mut() {
+            if let Payload::Nested(Nested::TcaStats(ref mut stats)) = a.rta_payload {
+                for s in stats.iter_mut().filter(|s| s.rta_type == app) {
+                    let mut c = Cursor::new(&s.rta_value[..]);
+                    let p = try!(Payload::read(t, &mut c, &s.rta_value, false, family));
+                    s.rta_payload = p;
+                }
+            }
+        }
+
+        Ok(())
+    }
+    /// TCA_KIND, the kind of qdisc, class or filter
+    pub fn kind(&self) -> Option<&str> {
+        string_attr(&self.tcm_attr, TcAttr::TCA_KIND)
+    }
+    /// The priority of a filter, the top 16 bits of tcm_info
+    pub fn prio(&self) -> Option<u16> {
+        if self.tcm_filter { Some((self.tcm_info >> 16) as u16) } else { None }
+    }
+    /// The ethertype that a filter is for, the bottom 16 bits of tcm_info,
+    /// which are big endian
+    pub fn protocol(&self) -> Option<Value<EthP>> {
+        if self.tcm_filter {
+            Some(Value::new(u16::from_be(self.tcm_info as u16) as u32))
+        } else {
+            None
+        }
+    }
+    pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        let i_s_p = format_indent(indent+1);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    tcm_family: {},\n", i_s, self.tcm_family));
+        try!(write!(f, "{}    tcm_ifindex: {},\n", i_s, self.tcm_ifindex));
+        if self.tcm_filter {
+            // a filter handle is whatever the classifier wants it to be, so
+            // it is in hex like tc filter show, not major:minor
+            try!(write!(f, "{}    tcm_handle: {:#x},\n", i_s, self.tcm_handle));
+        } else {
+            try!(write!(f, "{}    tcm_handle: {},\n", i_s, TcHandle(self.tcm_handle)));
+        }
+        try!(write!(f, "{}    tcm_parent: ", i_s));
+        try!(TcHandle(self.tcm_parent).fmt_for(f, self.kind()));
+        try!(write!(f, ",\n"));
+        match (self.prio(), self.protocol()) {
+            (Some(prio), Some(protocol)) => {
+                try!(write!(f, "{}    tcm_info: {:#X} (prio {}, protocol {}),\n",
+                            i_s, self.tcm_info, prio, protocol));
+            }
+            _ => try!(write!(f, "{}    tcm_info: {},\n", i_s, self.tcm_info)),
+        }
+
+        // TODO: macro? Or move into Rtattr?
+        try!(write!(f, "{}    tcm_attr: [ ", i_s));
+        let mut count: usize = 1;
+        for a in self.tcm_attr.iter() {
+            try!(a.pretty_fmt(f, indent+1));
+            if count < self.tcm_attr.len() {
+                try!(write!(f, ",\n{}", i_s_p));
+            }
+            count = count + 1;
+        }
+        write!(f, " ],\n{}}}", i_s)
+    }
+}
+impl ::std::fmt::Display for Tcmsg {
+    #[allow(dead_code)]
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        self.pretty_fmt(f, 0)
+    }
+}
+
+/// struct tc_ratespec, how a rate was given to tc. In tc_htb_opt and
+/// tc_tbf_qopt, and the rate tables next to them are computed from it.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcRatespec {
+    pub cell_log: u8,
+    pub linklayer: u8, // TC_LINKLAYER_*
+    pub overhead: u16,
+    pub cell_align: i16,
+    pub mpu: u16,
+    pub rate: u32, // bytes per second
+}
+impl TcRatespec {
+    // TcRatespec is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcRatespec> {
+        let mut s = TcRatespec::default();
+
+        s.cell_log = try!(read_u8(cursor, end, "tc_ratespec"));
+        s.linklayer = try!(read_u8(cursor, end, "tc_ratespec"));
+        s.overhead = try!(read_u16::<NativeEndian>(cursor, end, "tc_ratespec"));
+        s.cell_align = try!(read_u16::<NativeEndian>(cursor, end, "tc_ratespec")) as i16;
+        s.mpu = try!(read_u16::<NativeEndian>(cursor, end, "tc_ratespec"));
+        s.rate = try!(read_u32::<NativeEndian>(cursor, end, "tc_ratespec"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcRatespec {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    rate: {} ({}),\n", indent, self.rate, fmt_rate(self.rate as u64)));
+        try!(write!(f, "{}    cell_log: {},\n", indent, self.cell_log));
+        try!(write!(f, "{}    linklayer: {},\n", indent, self.linklayer));
+        try!(write!(f, "{}    overhead: {},\n", indent, self.overhead));
+        try!(write!(f, "{}    cell_align: {},\n", indent, self.cell_align));
+        try!(write!(f, "{}    mpu: {},\n", indent, self.mpu));
+        write!(f, "{}}}", indent)
+    }
+}
+
+/// A rate in bytes per second the way tc prints it, 125000 -> "1Mbit". Like
+/// tc, the unit is only made bigger while that doesn't lose anything, or
+/// the number would be 1000000 or more.
+pub fn fmt_rate(bytes: u64) -> String {
+    let units = ["bit", "Kbit", "Mbit", "Gbit", "Tbit"];
+    let mut rate = bytes.saturating_mul(8);
+    let mut i = 0;
+    while i < units.len() - 1 {
+        if rate < 1000 || (rate % 1000 != 0 && rate < 1000 * 1000) {
+            break;
+        }
+        rate /= 1000;
+        i += 1;
+    }
+    format!("{}{}", rate, units[i])
+}
+
+/// struct tc_htb_opt, for TCA_HTB_PARMS of an htb class
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcHtbOpt {
+    pub rate: TcRatespec,
+    pub ceil: TcRatespec,
+    pub buffer: u32, // in psched ticks, like cbuffer
+    pub cbuffer: u32,
+    pub quantum: u32,
+    pub level: u32,
+    pub prio: u32,
+}
+impl TcHtbOpt {
+    // TcHtbOpt is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcHtbOpt> {
+        let mut s = TcHtbOpt::default();
+
+        s.rate = try!(TcRatespec::read(cursor, end));
+        s.ceil = try!(TcRatespec::read(cursor, end));
+        s.buffer = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_opt"));
+        s.cbuffer = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_opt"));
+        s.quantum = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_opt"));
+        s.level = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_opt"));
+        s.prio = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_opt"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcHtbOpt {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    rate: ", i_s));
+        try!(self.rate.pretty_fmt(f, indent+1));
+        try!(write!(f, ",\n{}    ceil: ", i_s));
+        try!(self.ceil.pretty_fmt(f, indent+1));
+        try!(write!(f, ",\n"));
+        try!(write!(f, "{}    buffer: {},\n", i_s, self.buffer));
+        try!(write!(f, "{}    cbuffer: {},\n", i_s, self.cbuffer));
+        try!(write!(f, "{}    quantum: {},\n", i_s, self.quantum));
+        try!(write!(f, "{}    level: {},\n", i_s, self.level));
+        try!(write!(f, "{}    prio: {},\n", i_s, self.prio));
+        write!(f, "{}}}", i_s)
+    }
+}
+impl AttrStruct for TcHtbOpt {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_htb_glob, for TCA_HTB_INIT of an htb qdisc
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcHtbGlob {
+    pub version: u32,
+    pub rate2quantum: u32,
+    pub defcls: u32, // the minor of the default class
+    pub debug: u32,
+    pub direct_pkts: u32,
+}
+impl TcHtbGlob {
+    // TcHtbGlob is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcHtbGlob> {
+        let mut s = TcHtbGlob::default();
+
+        s.version = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_glob"));
+        s.rate2quantum = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_glob"));
+        s.defcls = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_glob"));
+        s.debug = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_glob"));
+        s.direct_pkts = try!(read_u32::<NativeEndian>(cursor, end, "tc_htb_glob"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcHtbGlob {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    version: {},\n", indent, self.version));
+        try!(write!(f, "{}    rate2quantum: {},\n", indent, self.rate2quantum));
+        try!(write!(f, "{}    defcls: {:#X},\n", indent, self.defcls));
+        try!(write!(f, "{}    debug: {},\n", indent, self.debug));
+        try!(write!(f, "{}    direct_pkts: {},\n", indent, self.direct_pkts));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcHtbGlob {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_tbf_qopt, for TCA_TBF_PARMS
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcTbfQopt {
+    pub rate: TcRatespec,
+    pub peakrate: TcRatespec,
+    pub limit: u32,
+    pub buffer: u32, // in psched ticks
+    pub mtu: u32,
+}
+impl TcTbfQopt {
+    // TcTbfQopt is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcTbfQopt> {
+        let mut s = TcTbfQopt::default();
+
+        s.rate = try!(TcRatespec::read(cursor, end));
+        s.peakrate = try!(TcRatespec::read(cursor, end));
+        s.limit = try!(read_u32::<NativeEndian>(cursor, end, "tc_tbf_qopt"));
+        s.buffer = try!(read_u32::<NativeEndian>(cursor, end, "tc_tbf_qopt"));
+        s.mtu = try!(read_u32::<NativeEndian>(cursor, end, "tc_tbf_qopt"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcTbfQopt {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    rate: ", i_s));
+        try!(self.rate.pretty_fmt(f, indent+1));
+        try!(write!(f, ",\n{}    peakrate: ", i_s));
+        try!(self.peakrate.pretty_fmt(f, indent+1));
+        try!(write!(f, ",\n"));
+        try!(write!(f, "{}    limit: {},\n", i_s, self.limit));
+        try!(write!(f, "{}    buffer: {},\n", i_s, self.buffer));
+        try!(write!(f, "{}    mtu: {},\n", i_s, self.mtu));
+        write!(f, "{}}}", i_s)
+    }
+}
+impl AttrStruct for TcTbfQopt {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_prio_qopt, the options of prio and pfifo_fast, and
+/// TCA_FQ_PRIOMAP
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcPrioQopt {
+    pub bands: i32,
+    pub priomap: [u8; 16], // the band of each TC_PRIO_*
+}
+impl TcPrioQopt {
+    // TcPrioQopt is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcPrioQopt> {
+        let mut s = TcPrioQopt::default();
+
+        s.bands = try!(read_i32::<NativeEndian>(cursor, end, "tc_prio_qopt"));
+        let b = try!(read_bytes(cursor, end, 16, "tc_prio_qopt"));
+        s.priomap.copy_from_slice(&b);
+
+        Ok(s)
+    }
+}
+impl Pretty for TcPrioQopt {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    bands: {},\n", indent, self.bands));
+        try!(write!(f, "{}    priomap: ", indent));
+        try!(fmt_numbers(f, &self.priomap));
+        try!(write!(f, ",\n"));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcPrioQopt {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_fifo_qopt, the options of pfifo and bfifo
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcFifoQopt {
+    pub limit: u32, // packets for pfifo, bytes for bfifo
+}
+impl TcFifoQopt {
+    // TcFifoQopt is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcFifoQopt> {
+        let mut s = TcFifoQopt::default();
+
+        s.limit = try!(read_u32::<NativeEndian>(cursor, end, "tc_fifo_qopt"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcFifoQopt {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    limit: {},\n", indent, self.limit));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcFifoQopt {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_netem_qopt, the options of netem. The TCA_NETEM_* attributes
+/// come after it in the same TCA_OPTIONS.
+#[derive(Debug, Default, Clone)]
+pub struct TcNetemQopt {
+    pub latency: u32, // in psched ticks, like jitter
+    pub limit: u32,
+    pub loss: u32, // 0xFFFFFFFF is 100%
+    pub gap: u32,
+    pub duplicate: u32,
+    pub jitter: u32,
+    pub attrs: Vec<Rtattr<TcaNetem>>,
+}
+impl TcNetemQopt {
+    // TcNetemQopt is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcNetemQopt> {
+        let mut s = TcNetemQopt::default();
+
+        s.latency = try!(read_u32::<NativeEndian>(cursor, end, "tc_netem_qopt"));
+        s.limit = try!(read_u32::<NativeEndian>(cursor, end, "tc_netem_qopt"));
+        s.loss = try!(read_u32::<NativeEndian>(cursor, end, "tc_netem_qopt"));
+        s.gap = try!(read_u32::<NativeEndian>(cursor, end, "tc_netem_qopt"));
+        s.duplicate = try!(read_u32::<NativeEndian>(cursor, end, "tc_netem_qopt"));
+        s.jitter = try!(read_u32::<NativeEndian>(cursor, end, "tc_netem_qopt"));
+        s.attrs = try!(read_attrs(cursor, end, Value::default()));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcNetemQopt {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    latency: {},\n", i_s, self.latency));
+        try!(write!(f, "{}    limit: {},\n", i_s, self.limit));
+        try!(write!(f, "{}    loss: {},\n", i_s, self.loss));
+        try!(write!(f, "{}    gap: {},\n", i_s, self.gap));
+        try!(write!(f, "{}    duplicate: {},\n", i_s, self.duplicate));
+        try!(write!(f, "{}    jitter: {},\n", i_s, self.jitter));
+        try!(write!(f, "{}    attrs: ", i_s));
+        try!(fmt_attrs(f, &self.attrs, indent));
+        write!(f, ",\n{}}}", i_s)
+    }
+}
+impl AttrStruct for TcNetemQopt {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_sfq_qopt, the options of sfq. Kernels send struct
+/// tc_sfq_qopt_v1, we go as far as depth and headdrop from that.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcSfqQopt {
+    pub quantum: u32,
+    pub perturb_period: i32, // seconds
+    pub limit: u32,
+    pub divisor: u32,
+    pub flows: u32,
+    pub depth: Option<u32>,
+    pub headdrop: Option<u32>,
+}
+impl TcSfqQopt {
+    // TcSfqQopt is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcSfqQopt> {
+        let mut s = TcSfqQopt::default();
+
+        s.quantum = try!(read_u32::<NativeEndian>(cursor, end, "tc_sfq_qopt"));
+        s.perturb_period = try!(read_i32::<NativeEndian>(cursor, end, "tc_sfq_qopt"));
+        s.limit = try!(read_u32::<NativeEndian>(cursor, end, "tc_sfq_qopt"));
+        s.divisor = try!(read_u32::<NativeEndian>(cursor, end, "tc_sfq_qopt"));
+        s.flows = try!(read_u32::<NativeEndian>(cursor, end, "tc_sfq_qopt"));
+        if cursor.position() as usize + 8 <= end {
+            s.depth = Some(try!(read_u32::<NativeEndian>(cursor, end, "tc_sfq_qopt_v1")));
+            s.headdrop = Some(try!(read_u32::<NativeEndian>(cursor, end, "tc_sfq_qopt_v1")));
+        }
+
+        Ok(s)
+    }
+}
+impl Pretty for TcSfqQopt {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    quantum: {},\n", indent, self.quantum));
+        try!(write!(f, "{}    perturb_period: {},\n", indent, self.perturb_period));
+        try!(write!(f, "{}    limit: {},\n", indent, self.limit));
+        try!(write!(f, "{}    divisor: {},\n", indent, self.divisor));
+        try!(write!(f, "{}    flows: {},\n", indent, self.flows));
+        if let Some(depth) = self.depth {
+            try!(write!(f, "{}    depth: {},\n", indent, depth));
+        }
+        if let Some(headdrop) = self.headdrop {
+            try!(write!(f, "{}    headdrop: {},\n", indent, headdrop));
+        }
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcSfqQopt {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_u32_key, one 32 bit match of a u32 selector
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcU32Key {
+    pub mask: u32,
+    pub val: u32,
+    pub off: i32,
+    pub offmask: i32,
+}
+impl fmt::Display for TcU32Key {
+    /// The way tc prints them, match 0a000001/ffffffff at 16
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "match {:08x}/{:08x} at ", self.val, self.mask));
+        if self.offmask != 0 {
+            try!(write!(f, "nexthdr+"));
+        }
+        write!(f, "{}", self.off)
+    }
+}
+
+/// struct tc_u32_sel, for TCA_U32_SEL, with its keys
+#[derive(Debug, Default, Clone)]
+pub struct TcU32Sel {
+    pub flags: u8, // TC_U32_*
+    pub offshift: u8,
+    pub nkeys: u8,
+    pub offmask: u16,
+    pub off: u16,
+    pub offoff: i16,
+    pub hoff: i16,
+    pub hmask: u32,
+    pub keys: Vec<TcU32Key>,
+}
+impl TcU32Sel {
+    // TcU32Sel is native endian, except for offmask, hmask and the mask and
+    // val of the keys
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcU32Sel> {
+        let mut s = TcU32Sel::default();
+
+        s.flags = try!(read_u8(cursor, end, "tc_u32_sel"));
+        s.offshift = try!(read_u8(cursor, end, "tc_u32_sel"));
+        s.nkeys = try!(read_u8(cursor, end, "tc_u32_sel"));
+        let mut _pad: u8 = 0;
+        _pad = try!(read_u8(cursor, end, "tc_u32_sel"));
+        s.offmask = try!(read_u16::<BigEndian>(cursor, end, "tc_u32_sel"));
+        s.off = try!(read_u16::<NativeEndian>(cursor, end, "tc_u32_sel"));
+        s.offoff = try!(read_u16::<NativeEndian>(cursor, end, "tc_u32_sel")) as i16;
+        s.hoff = try!(read_u16::<NativeEndian>(cursor, end, "tc_u32_sel")) as i16;
+        s.hmask = try!(read_u32::<BigEndian>(cursor, end, "tc_u32_sel"));
+        for _ in 0..s.nkeys {
+            let mut k = TcU32Key::default();
+            k.mask = try!(read_u32::<BigEndian>(cursor, end, "tc_u32_key"));
+            k.val = try!(read_u32::<BigEndian>(cursor, end, "tc_u32_key"));
+            k.off = try!(read_i32::<NativeEndian>(cursor, end, "tc_u32_key"));
+            k.offmask = try!(read_i32::<NativeEndian>(cursor, end, "tc_u32_key"));
+            s.keys.push(k);
+        }
+
+        Ok(s)
+    }
+}
+impl Pretty for TcU32Sel {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    flags: {:#X} ({}),\n", indent, self.flags,
+                    Flags::<TcU32Flags>::new(self.flags as u32)));
+        try!(write!(f, "{}    offshift: {},\n", indent, self.offshift));
+        try!(write!(f, "{}    nkeys: {},\n", indent, self.nkeys));
+        try!(write!(f, "{}    offmask: {:#X},\n", indent, self.offmask));
+        try!(write!(f, "{}    off: {},\n", indent, self.off));
+        try!(write!(f, "{}    offoff: {},\n", indent, self.offoff));
+        try!(write!(f, "{}    hoff: {},\n", indent, self.hoff));
+        try!(write!(f, "{}    hmask: {:#X},\n", indent, self.hmask));
+        try!(write!(f, "{}    keys: [", indent));
+        for (i, k) in self.keys.iter().enumerate() {
+            try!(write!(f, "{} {}", if i == 0 { "" } else { "," }, k));
+        }
+        try!(write!(f, " ],\n"));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcU32Sel {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_u32_mark, for TCA_U32_MARK
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcU32Mark {
+    pub val: u32,
+    pub mask: u32,
+    pub success: u32,
+}
+impl TcU32Mark {
+    // TcU32Mark is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcU32Mark> {
+        let mut s = TcU32Mark::default();
+
+        s.val = try!(read_u32::<NativeEndian>(cursor, end, "tc_u32_mark"));
+        s.mask = try!(read_u32::<NativeEndian>(cursor, end, "tc_u32_mark"));
+        s.success = try!(read_u32::<NativeEndian>(cursor, end, "tc_u32_mark"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcU32Mark {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    val: {:#X},\n", indent, self.val));
+        try!(write!(f, "{}    mask: {:#X},\n", indent, self.mask));
+        try!(write!(f, "{}    success: {},\n", indent, self.success));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcU32Mark {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// The verdict of an action the way tc names it. TC_ACT_JUMP and
+/// TC_ACT_GOTO_CHAIN have how far or which chain in the bits under them.
+pub fn fmt_tc_action(action: i32) -> String {
+    const TC_ACT_EXT_VAL_MASK: u32 = 0x0FFFFFFF;
+    const TC_ACT_JUMP: u32 = 0x10000000;
+    const TC_ACT_GOTO_CHAIN: u32 = 0x20000000;
+    let a = action as u32;
+    match a & !TC_ACT_EXT_VAL_MASK {
+        _ if action == -1 => "TC_ACT_UNSPEC".to_string(),
+        TC_ACT_JUMP => format!("TC_ACT_JUMP {}", a & TC_ACT_EXT_VAL_MASK),
+        TC_ACT_GOTO_CHAIN => format!("TC_ACT_GOTO_CHAIN {}", a & TC_ACT_EXT_VAL_MASK),
+        _ => Value::<TcAct>::new(a).to_string(),
+    }
+}
+
+/// struct tcf_t, for TCA_*_TM of actions. In clock_t, hundredths of a
+/// second.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Tcft {
+    pub install: u64,
+    pub lastuse: u64,
+    pub expires: u64,
+    pub firstuse: u64,
+}
+impl Tcft {
+    // Tcft is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<Tcft> {
+        let mut s = Tcft::default();
+
+        s.install = try!(read_u64::<NativeEndian>(cursor, end, "tcf_t"));
+        s.lastuse = try!(read_u64::<NativeEndian>(cursor, end, "tcf_t"));
+        s.expires = try!(read_u64::<NativeEndian>(cursor, end, "tcf_t"));
+        s.firstuse = try!(read_u64::<NativeEndian>(cursor, end, "tcf_t"));
+
+        Ok(s)
+    }
+}
+impl Pretty for Tcft {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    install: {},\n", indent, self.install));
+        try!(write!(f, "{}    lastuse: {},\n", indent, self.lastuse));
+        try!(write!(f, "{}    expires: {},\n", indent, self.expires));
+        try!(write!(f, "{}    firstuse: {},\n", indent, self.firstuse));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for Tcft {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// tc_gen, the fields that the parms of every action start with. struct
+/// tc_gact and struct tc_skbedit are only this.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcGen {
+    pub index: u32,
+    pub capab: u32,
+    pub action: i32, // TC_ACT_*
+    pub refcnt: i32,
+    pub bindcnt: i32,
+}
+impl TcGen {
+    // TcGen is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcGen> {
+        let mut s = TcGen::default();
+
+        s.index = try!(read_u32::<NativeEndian>(cursor, end, "tc_gen"));
+        s.capab = try!(read_u32::<NativeEndian>(cursor, end, "tc_gen"));
+        s.action = try!(read_i32::<NativeEndian>(cursor, end, "tc_gen"));
+        s.refcnt = try!(read_i32::<NativeEndian>(cursor, end, "tc_gen"));
+        s.bindcnt = try!(read_i32::<NativeEndian>(cursor, end, "tc_gen"));
+
+        Ok(s)
+    }
+    /// The fields without the braces, for the structs that start with tc_gen
+    fn fmt_fields(&self, f: &mut fmt::Formatter, indent: &str) -> fmt::Result {
+        try!(write!(f, "{}    index: {},\n", indent, self.index));
+        try!(write!(f, "{}    capab: {},\n", indent, self.capab));
+        try!(write!(f, "{}    action: {},\n", indent, fmt_tc_action(self.action)));
+        try!(write!(f, "{}    refcnt: {},\n", indent, self.refcnt));
+        write!(f, "{}    bindcnt: {},\n", indent, self.bindcnt)
+    }
+}
+impl Pretty for TcGen {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(self.fmt_fields(f, &indent));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcGen {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_gact_p, for TCA_GACT_PROB
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcGactP {
+    pub ptype: u16, // PGACT_NONE, PGACT_NETRAND or PGACT_DETERM
+    pub pval: u16,
+    pub paction: i32, // TC_ACT_*
+}
+impl TcGactP {
+    // TcGactP is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcGactP> {
+        let mut s = TcGactP::default();
+
+        s.ptype = try!(read_u16::<NativeEndian>(cursor, end, "tc_gact_p"));
+        s.pval = try!(read_u16::<NativeEndian>(cursor, end, "tc_gact_p"));
+        s.paction = try!(read_i32::<NativeEndian>(cursor, end, "tc_gact_p"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcGactP {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        let ptype = match self.ptype {
+            0 => "PGACT_NONE",
+            1 => "PGACT_NETRAND",
+            2 => "PGACT_DETERM",
+            _ => "",
+        };
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    ptype: {} ({}),\n", indent, self.ptype, ptype));
+        try!(write!(f, "{}    pval: {},\n", indent, self.pval));
+        try!(write!(f, "{}    paction: {},\n", indent, fmt_tc_action(self.paction)));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcGactP {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_mirred, for TCA_MIRRED_PARMS
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcMirred {
+    pub gen: TcGen,
+    pub eaction: Value<TcaMirredEaction>,
+    pub ifindex: u32,
+}
+impl TcMirred {
+    // TcMirred is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcMirred> {
+        let mut s = TcMirred::default();
+
+        s.gen = try!(TcGen::read(cursor, end));
+        s.eaction = Value::new(try!(read_i32::<NativeEndian>(cursor, end, "tc_mirred")) as u32);
+        s.ifindex = try!(read_u32::<NativeEndian>(cursor, end, "tc_mirred"));
+
+        Ok(s)
+    }
+}
+impl Pretty for TcMirred {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(self.gen.fmt_fields(f, &indent));
+        try!(write!(f, "{}    eaction: {},\n", indent, self.eaction));
+        try!(write!(f, "{}    ifindex: {},\n", indent, self.ifindex));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcMirred {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_vlan, for TCA_VLAN_PARMS
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcVlan {
+    pub gen: TcGen,
+    pub v_action: Value<TcaVlanAct>,
+}
+impl TcVlan {
+    // TcVlan is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcVlan> {
+        let mut s = TcVlan::default();
+
+        s.gen = try!(TcGen::read(cursor, end));
+        s.v_action = Value::new(try!(read_i32::<NativeEndian>(cursor, end, "tc_vlan")) as u32);
+
+        Ok(s)
+    }
+}
+impl Pretty for TcVlan {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(self.gen.fmt_fields(f, &indent));
+        try!(write!(f, "{}    v_action: {},\n", indent, self.v_action));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcVlan {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_tunnel_key, for TCA_TUNNEL_KEY_PARMS
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcTunnelKey {
+    pub gen: TcGen,
+    pub t_action: Value<TcaTunnelKeyAct>,
+}
+impl TcTunnelKey {
+    // TcTunnelKey is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcTunnelKey> {
+        let mut s = TcTunnelKey::default();
+
+        s.gen = try!(TcGen::read(cursor, end));
+        s.t_action = Value::new(try!(read_i32::<NativeEndian>(cursor, end, "tc_tunnel_key")) as u32);
+
+        Ok(s)
+    }
+}
+impl Pretty for TcTunnelKey {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(self.gen.fmt_fields(f, &indent));
+        try!(write!(f, "{}    t_action: {},\n", indent, self.t_action));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcTunnelKey {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tc_pedit_key, one 32 bit edit of pedit
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcPeditKey {
+    pub mask: u32, // what is kept
+    pub val: u32, // what is xor'd in
+    pub off: i32,
+    pub at: u32,
+    pub offmask: u32,
+    pub shift: u32,
+}
+impl fmt::Display for TcPeditKey {
+    /// The way tc prints them, at 12: val 0a000001 mask 00000000
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "at {}: val {:08x} mask {:08x}", self.off, self.val, self.mask));
+        if self.offmask != 0 {
+            try!(write!(f, " at {} offmask {:08x} shift {}", self.at, self.offmask, self.shift));
+        }
+        Ok(())
+    }
+}
+
+/// struct tc_pedit_sel, for TCA_PEDIT_PARMS and TCA_PEDIT_PARMS_EX, with
+/// its keys
+#[derive(Debug, Default, Clone)]
+pub struct TcPeditSel {
+    pub gen: TcGen,
+    pub nkeys: u8,
+    pub flags: u8,
+    pub keys: Vec<TcPeditKey>,
+}
+impl TcPeditSel {
+    // TcPeditSel is native endian, except for the mask and val of the keys
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<TcPeditSel> {
+        let mut s = TcPeditSel::default();
+
+        s.gen = try!(TcGen::read(cursor, end));
+        s.nkeys = try!(read_u8(cursor, end, "tc_pedit_sel"));
+        s.flags = try!(read_u8(cursor, end, "tc_pedit_sel"));
+        let mut _pad: u16 = 0;
+        _pad = try!(read_u16::<NativeEndian>(cursor, end, "tc_pedit_sel"));
+        for _ in 0..s.nkeys {
+            let mut k = TcPeditKey::default();
+            k.mask = try!(read_u32::<BigEndian>(cursor, end, "tc_pedit_key"));
+            k.val = try!(read_u32::<BigEndian>(cursor, end, "tc_pedit_key"));
+            k.off = try!(read_i32::<NativeEndian>(cursor, end, "tc_pedit_key"));
+            k.at = try!(read_u32::<NativeEndian>(cursor, end, "tc_pedit_key"));
+            k.offmask = try!(read_u32::<NativeEndian>(cursor, end, "tc_pedit_key"));
+            k.shift = try!(read_u32::<NativeEndian>(cursor, end, "tc_pedit_key"));
+            s.keys.push(k);
+        }
+
+        Ok(s)
+    }
+}
+impl Pretty for TcPeditSel {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let indent = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(self.gen.fmt_fields(f, &indent));
+        try!(write!(f, "{}    nkeys: {},\n", indent, self.nkeys));
+        try!(write!(f, "{}    flags: {:#X},\n", indent, self.flags));
+        try!(write!(f, "{}    keys: [", indent));
+        for (i, k) in self.keys.iter().enumerate() {
+            try!(write!(f, "{} {}", if i == 0 { "" } else { "," }, k));
+        }
+        try!(write!(f, " ],\n"));
+        write!(f, "{}}}", indent)
+    }
+}
+impl AttrStruct for TcPeditSel {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct tcamsg, of RTM_NEWACTION, RTM_DELACTION and RTM_GETACTION
+#[derive(Debug, Default, Clone)]
+pub struct Tcamsg {
+    pub tca_family: Value<AddressFamily>,
+    pub tca_attr: Vec<Rtattr<TcaRoot>>,
+}
+impl Tcamsg {
+    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<Tcamsg> {
+        let mut s = Tcamsg::default();
+        try!(s.read_into(cursor, nlmsg_len));
+        Ok(s)
+    }
+    // Tcamsg header is native endian
+    /// Like read(), but what was decoded before an error is left in self
+    pub fn read_into(&mut self, cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<()> {
+        try!(check_len(cursor, nlmsg_len, 4, "tcamsg"));
+        let family_u8: u8;
+        family_u8 = try!(read_u8(cursor, nlmsg_len, "tcamsg"));
+        let mut _tca_pad_u8: u8 = 0;
+        _tca_pad_u8 = try!(read_u8(cursor, nlmsg_len, "tcamsg"));
+        let mut _tca_pad_u16: u16 = 0;
+        _tca_pad_u16 = try!(read_u16::<NativeEndian>(cursor, nlmsg_len, "tcamsg"));
+
+        self.tca_family = Value::new(family_u8 as u32);
+        try!(read_attrs_into(cursor, nlmsg_len, self.tca_family, &mut self.tca_attr));
+
+        Ok(())
+    }
+    pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    tca_family: {},\n", i_s, self.tca_family));
+        try!(write!(f, "{}    tca_attr: ", i_s));
+        try!(fmt_attrs(f, &self.tca_attr, indent));
+        write!(f, ",\n{}}}", i_s)
+    }
+}
+impl ::std::fmt::Display for Tcamsg {
+    #[allow(dead_code)]
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        self.pretty_fmt(f, 0)
+    }
+}
+
+/// struct fib_rule_uid_range, for FRA_UID_RANGE
+#[derive(Debug, Default, Copy, Clone)]
+pub struct FibRuleUidRange {
+    pub start: u32,
+    pub end: u32,
+}
+impl FibRuleUidRange {
+    // FibRuleUidRange is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<FibRuleUidRange> {
+        let mut s = FibRuleUidRange::default();
+
+        s.start = try!(read_u32::<NativeEndian>(cursor, end, "fib_rule_uid_range"));
+        s.end = try!(read_u32::<NativeEndian>(cursor, end, "fib_rule_uid_range"));
+
+        Ok(s)
+    }
+}
+impl Pretty for FibRuleUidRange {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, _: i32) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+impl AttrStruct for FibRuleUidRange {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct fib_rule_port_range, for FRA_SPORT_RANGE and FRA_DPORT_RANGE
+#[derive(Debug, Default, Copy, Clone)]
+pub struct FibRulePortRange {
+    pub start: u16,
+    pub end: u16,
+}
+impl FibRulePortRange {
+    // FibRulePortRange is native endian
+    pub fn read(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<FibRulePortRange> {
+        let mut s = FibRulePortRange::default();
+
+        s.start = try!(read_u16::<NativeEndian>(cursor, end, "fib_rule_port_range"));
+        s.end = try!(read_u16::<NativeEndian>(cursor, end, "fib_rule_port_range"));
+
+        Ok(s)
+    }
+}
+impl Pretty for FibRulePortRange {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, _: i32) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+impl AttrStruct for FibRulePortRange {
+    fn box_clone(&self) -> Box<AttrStruct> {
+        Box::new(self.clone())
+    }
+}
+
+/// struct fib_rule_hdr, of RTM_NEWRULE, RTM_DELRULE and RTM_GETRULE
+#[derive(Debug, Default, Clone)]
+pub struct FibRuleHdr {
+    pub family: Value<AddressFamily>,
+    pub dst_len: u8,
+    pub src_len: u8,
+    pub tos: u8,
+    pub table: u8, // Routing table ID, FRA_TABLE has the ones above 255
+    pub action: Value<FrAct>,
+    pub flags: u32, // FIB_RULE_*
+    pub fra_attr: Vec<Rtattr<Fra>>,
+}
+impl FibRuleHdr {
+    pub fn read(cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<FibRuleHdr> {
+        let mut s = FibRuleHdr::default();
+        try!(s.read_into(cursor, nlmsg_len));
+        Ok(s)
+    }
+    // FibRuleHdr header is native endian
+    /// Like read(), but what was decoded before an error is left in self
+    pub fn read_into(&mut self, cursor: &mut Cursor<&[u8]>, nlmsg_len: usize) -> error::Result<()> {
+        try!(check_len(cursor, nlmsg_len, 12, "fib_rule_hdr"));
+        let family_u8: u8;
+        family_u8 = try!(read_u8(cursor, nlmsg_len, "fib_rule_hdr"));
+        self.dst_len = try!(read_u8(cursor, nlmsg_len, "fib_rule_hdr"));
+        self.src_len = try!(read_u8(cursor, nlmsg_len, "fib_rule_hdr"));
+        self.tos = try!(read_u8(cursor, nlmsg_len, "fib_rule_hdr"));
+
+        self.table = try!(read_u8(cursor, nlmsg_len, "fib_rule_hdr"));
+        let mut _res1_u8: u8 = 0;
+        _res1_u8 = try!(read_u8(cursor, nlmsg_len, "fib_rule_hdr"));
+        let mut _res2_u8: u8 = 0;
+        _res2_u8 = try!(read_u8(cursor, nlmsg_len, "fib_rule_hdr"));
+        let action_u8: u8;
+        action_u8 = try!(read_u8(cursor, nlmsg_len, "fib_rule_hdr"));
+
+        self.flags = try!(read_u32::<NativeEndian>(cursor, nlmsg_len, "fib_rule_hdr"));
+
+        self.family = Value::new(family_u8 as u32);
+        self.action = Value::new(action_u8 as u32);
+
+        try!(read_attrs_into(cursor, nlmsg_len, self.family, &mut self.fra_attr));
+        // FRA_DST and FRA_SRC are printed in CIDR form, like RTA_DST
+        for attr in self.fra_attr.iter_mut() {
+            let len = match attr.rta_type {
+                Value::Known(Fra::FRA_DST) => Some(self.dst_len),
+                Value::Known(Fra::FRA_SRC) => Some(self.src_len),
+                _ => None,
+            };
+            match (&attr.rta_payload, len) {
+                (&Payload::Ip(ip), Some(len)) => attr.rta_payload = Payload::Prefix(ip, len),
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+    pub fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    family: {},\n", i_s, self.family));
+        try!(write!(f, "{}    dst_len: {},\n", i_s, self.dst_len));
+        try!(write!(f, "{}    src_len: {},\n", i_s, self.src_len));
+        try!(write!(f, "{}    tos: {},\n", i_s, self.tos));
+        try!(write!(f, "{}    table: {} (", i_s, self.table()));
+        try!(RtTable::pretty_fmt(f, self.table()));
+        try!(write!(f, "),\n{}    action: {},\n", i_s, self.action));
+        try!(write!(f, "{}    flags: {:#X} (", i_s, self.flags));
+        try!(fmt_flags::<FibRuleFlags>(f, self.flags));
+        try!(write!(f, "),\n{}    rule: {},\n", i_s, self.rule()));
+        try!(write!(f, "{}    fra_attr: ", i_s));
+        try!(fmt_attrs(f, &self.fra_attr, indent));
+        write!(f, ",\n{}}}", i_s)
+    }
+    fn attr(&self, t: Fra) -> Option<&Rtattr<Fra>> {
+        let t = Value::Known(t);
+        self.fra_attr.iter().find(|a| a.rta_type == t)
+    }
+    fn u32_attr(&self, t: Fra) -> Option<u32> {
+        match self.attr(t).map(|a| &a.rta_payload) {
+            Some(&Payload::U32(n)) => Some(n),
+            _ => None,
+        }
+    }
+    /// The routing table to look up. The table field only has room for
+    /// 255, so tables above that are in FRA_TABLE.
+    pub fn table(&self) -> u32 {
+        self.u32_attr(Fra::FRA_TABLE).unwrap_or(self.table as u32)
+    }
+    /// The line that `ip rule show` prints for this rule, with interface
+    /// names, tables and protocols as numbers where it would look them up
+    pub fn rule(&self) -> String {
+        let mut v: Vec<String> = Vec::new();
+        let has_flag = |flag: u32| self.flags & flag != 0;
+        // from/to print the address alone when it is a host
+        let prefix = |t: Fra, len: u8| match self.attr(t).map(|a| &a.rta_payload) {
+            Some(&Payload::Prefix(IpAddr::V4(ip), 32)) => Some(ip.to_string()),
+            Some(&Payload::Prefix(IpAddr::V6(ip), 128)) => Some(ip.to_string()),
+            Some(&Payload::Prefix(ip, len)) => Some(format!("{}/{}", ip, len)),
+            _ if len != 0 => Some(format!("0/{}", len)),
+            _ => None,
+        };
+
+        v.push(format!("{}:", self.u32_attr(Fra::FRA_PRIORITY).unwrap_or(0)));
+        if has_flag(FibRuleFlags::FIB_RULE_INVERT as u32) {
+            v.push("not".to_string());
+        }
+        v.push(format!("from {}", prefix(Fra::FRA_SRC, self.src_len).unwrap_or("all".to_string())));
+        if let Some(dst) = prefix(Fra::FRA_DST, self.dst_len) {
+            v.push(format!("to {}", dst));
+        }
+        if self.tos != 0 {
+            v.push(format!("tos {:#x}", self.tos));
+        }
+        let mark = self.u32_attr(Fra::FRA_FWMARK);
+        let mask = self.u32_attr(Fra::FRA_FWMASK);
+        if mark.is_some() || mask.is_some() {
+            match (mark.unwrap_or(0), mask.unwrap_or(0xFFFFFFFF)) {
+                (mark, 0xFFFFFFFF) => v.push(format!("fwmark {:#x}", mark)),
+                (mark, mask) => v.push(format!("fwmark {:#x}/{:#x}", mark, mask)),
+            }
+        }
+        let ifnames = [(Fra::FRA_IIFNAME, "iif", FibRuleFlags::FIB_RULE_IIF_DETACHED as u32),
+                       (Fra::FRA_OIFNAME, "oif", FibRuleFlags::FIB_RULE_OIF_DETACHED as u32)];
+        for &(t, name, detached) in ifnames.iter() {
+            if let Some(&Payload::String(ref s)) = self.attr(t).map(|a| &a.rta_payload) {
+                v.push(format!("{} {}", name, s));
+                if has_flag(detached) {
+                    v.push("[detached]".to_string());
+                }
+            }
+        }
+        if let Some(&Payload::U8(n)) = self.attr(Fra::FRA_L3MDEV).map(|a| &a.rta_payload) {
+            if n != 0 {
+                v.push("lookup [l3mdev-table]".to_string());
+            }
+        }
+        if let Some(a) = self.attr(Fra::FRA_UID_RANGE) {
+            let mut cursor = Cursor::new(&a.rta_value[..]);
+            if let Ok(r) = FibRuleUidRange::read(&mut cursor, a.rta_value.len()) {
+                v.push(format!("uidrange {}-{}", r.start, r.end));
+            }
+        }
+        if let Some(&Payload::Enum(_, ref name)) = self.attr(Fra::FRA_IP_PROTO).map(|a| &a.rta_payload) {
+            v.push(format!("ipproto {}", name.replace("IPPROTO_", "").to_lowercase()));
+        }
+        let ports = [(Fra::FRA_SPORT_RANGE, "sport"), (Fra::FRA_DPORT_RANGE, "dport")];
+        for &(t, name) in ports.iter() {
+            if let Some(a) = self.attr(t) {
+                let mut cursor = Cursor::new(&a.rta_value[..]);
+                match FibRulePortRange::read(&mut cursor, a.rta_value.len()) {
+                    Ok(r) if r.start == r.end => v.push(format!("{} {}", name, r.start)),
+                    Ok(r) => v.push(format!("{} {}-{}", name, r.start, r.end)),
+                    Err(_) => (),
+                }
+            }
+        }
+        if let Some(&Payload::U64(n)) = self.attr(Fra::FRA_TUN_ID).map(|a| &a.rta_payload) {
+            v.push(format!("tun_id {}", n));
+        }
+        let table = self.table();
+        if table != 0 {
+            v.push(match table {
+                253 => "lookup default".to_string(),
+                254 => "lookup main".to_string(),
+                255 => "lookup local".to_string(),
+                _ => format!("lookup {}", table),
+            });
+            let suppress = [(Fra::FRA_SUPPRESS_PREFIXLEN, "suppress_prefixlength"),
+                            (Fra::FRA_SUPPRESS_IFGROUP, "suppress_ifgroup")];
+            for &(t, name) in suppress.iter() {
+                match self.attr(t).map(|a| &a.rta_payload) {
+                    Some(&Payload::S32(n)) if n != -1 => v.push(format!("{} {}", name, n)),
+                    _ => (),
+                }
+            }
+        }
+        if let Some(flow) = self.u32_attr(Fra::FRA_FLOW) {
+            match (flow >> 16, flow & 0xFFFF) {
+                (0, to) => v.push(format!("realms {}", to)),
+                (from, to) => v.push(format!("realms {}/{}", from, to)),
+            }
+        }
+        match self.action {
+            Value::Known(FrAct::FR_ACT_TO_TBL) => (),
+            Value::Known(FrAct::FR_ACT_GOTO) => {
+                match self.u32_attr(Fra::FRA_GOTO) {
+                    Some(n) => v.push(format!("goto {}", n)),
+                    None => v.push("goto none".to_string()),
+                }
+                if has_flag(FibRuleFlags::FIB_RULE_UNRESOLVED as u32) {
+                    v.push("[unresolved]".to_string());
+                }
+            }
+            Value::Known(FrAct::FR_ACT_NOP) => v.push("nop".to_string()),
+            Value::Known(FrAct::FR_ACT_BLACKHOLE) => v.push("blackhole".to_string()),
+            Value::Known(FrAct::FR_ACT_UNREACHABLE) => v.push("unreachable".to_string()),
+            Value::Known(FrAct::FR_ACT_PROHIBIT) => v.push("prohibit".to_string()),
+            ref a => v.push(format!("action {}", a)),
+        }
+        v.join(" ")
+    }
+}
+impl ::std::fmt::Display for FibRuleHdr {
+    #[allow(dead_code)]
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        self.pretty_fmt(f, 0)
+    }
+}
+
+#[test]
+fn test_Rtattr_read_nested() {
+    let raw_data = [16u8, 0, 18, 0x80, 9, 0, 1, 0, 118, 101, 116, 104, 0, 0, 0, 0,
+                    12, 0, 60, 0x80, 8, 0, 1, 0x40, 0, 0, 0, 5];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let v = read_attrs::<Ifla>(&mut cursor, raw_data.len(), Value::default()).unwrap();
+    assert!(v.len() == 2);
+
+    assert!(v[0].rta_type == Value::Known(Ifla::IFLA_LINKINFO));
+    assert!(v[0].is_nested());
+    match v[0].rta_payload {
+        Payload::Nested(Nested::IflaInfo(ref c)) => {
+            assert!(c.len() == 1);
+            assert!(c[0].rta_type == Value::Known(IflaInfo::IFLA_INFO_KIND));
+            assert!(c[0].rta_value == b"veth\0");
+        }
+        _ => panic!("expected IflaInfo attributes"),
+    }
+
+    // a type we don't know, but the flag says what's inside
+    assert!(v[1].rta_type == Value::Unknown(60));
+    match v[1].rta_payload {
+        Payload::Nested(Nested::Raw(ref c)) => {
+            assert!(c.len() == 1);
+            assert!(c[0].rta_type == Value::Known(1));
+            assert!(c[0].is_net_byteorder());
+            assert!(c[0].rta_value == [0, 0, 0, 5]);
+        }
+        _ => panic!("expected Raw attributes"),
+    }
+}
+
+#[test]
+fn test_Rtattr_read_payload() {
+    // RTA_DST 10.1.2.0, RTA_OIF 3, RTA_METRICS with RTAX_MTU 1400, and an
+    // RTA_PRIORITY that is too short to be a u32
+    let raw_data = [8u8, 0, 1, 0, 10, 1, 2, 0, 8, 0, 4, 0, 3, 0, 0, 0,
+                    12, 0, 8, 0, 8, 0, 2, 0, 120, 5, 0, 0, 6, 0, 6, 0, 1, 0, 0, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let v = read_attrs::<RtmAttr>(&mut cursor, raw_data.len(),
+                                  Value::Known(AddressFamily::AF_INET)).unwrap();
+    assert!(v.len() == 4);
+    match v[0].rta_payload {
+        Payload::Ip(ip) => assert!(ip == IpAddr::V4(Ipv4Addr::new(10, 1, 2, 0))),
+        _ => panic!("expected an IP"),
+    }
+    match v[1].rta_payload {
+        Payload::U32(n) => assert!(n == 3),
+        _ => panic!("expected a u32"),
+    }
+    match v[2].rta_payload {
+        Payload::Nested(Nested::Rtax(ref c)) => {
+            assert!(c[0].rta_type == Value::Known(Rtax::RTAX_MTU));
+            match c[0].rta_payload {
+                Payload::U32(n) => assert!(n == 1400),
+                _ => panic!("expected a u32"),
+            }
+        }
+        _ => panic!("expected Rtax attributes"),
+    }
+    match v[3].rta_payload {
+        Payload::Bytes => assert!(v[3].rta_value == [1, 0]),
+        _ => panic!("expected bytes"),
+    }
+}
+
+#[test]
+fn test_Rtattr_read_ifla() {
+    // IFLA_OPERSTATE up, an IFLA_LINKMODE that we have no name for, and an
+    // IFLA_PHYS_SWITCH_ID
+    let raw_data = [5u8, 0, 16, 0, 6, 0, 0, 0, 5, 0, 17, 0, 9, 0, 0, 0,
+                    8, 0, 36, 0, 0xa, 0xb, 0xc, 0xd];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let v = read_attrs::<Ifla>(&mut cursor, raw_data.len(),
+                               Value::Known(AddressFamily::AF_UNSPEC)).unwrap();
+    assert!(v.len() == 3);
+    match v[0].rta_payload {
+        Payload::Enum(n, ref name) => assert!(n == 6 && name == "IF_OPER_UP"),
+        _ => panic!("expected an enum"),
+    }
+    match v[1].rta_payload {
+        Payload::Enum(n, ref name) => assert!(n == 9 && name == "Unknown(9)"),
+        _ => panic!("expected an enum"),
+    }
+    match v[2].rta_payload {
+        Payload::Hex(ref h) => assert!(h == &[0xa, 0xb, 0xc, 0xd]),
+        _ => panic!("expected hex"),
+    }
+}
+
+#[test]
+fn test_RtnlLinkStats64_read() {
+    // an older kernel, without rx_nohandler and rx_otherhost_dropped
+    let mut raw_data = vec![0u8; 23 * 8];
+    raw_data[0] = 3; // rx_packets
+    raw_data[3 * 8] = 200; // tx_bytes
+    raw_data[12 * 8] = 1; // rx_crc_errors
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let s = RtnlLinkStats64::read(&mut cursor, raw_data.len()).unwrap();
+    assert!(s.rx_packets == 3);
+    assert!(s.tx_bytes == 200);
+    assert!(s.rx_crc_errors == 1);
+    assert!(s.rx_nohandler == 0);
+    let counters = s.counters();
+    assert!(counters.len() == 25);
+    assert!(counters[12] == ("rx_crc_errors", 1));
+
+    // half of a counter is too short
+    let mut cursor = Cursor::new(&raw_data[..20]);
+    assert!(RtnlLinkStats64::read(&mut cursor, 20).is_err());
+}
+
+#[test]
+fn test_Rtattr_read_linkinfo() {
+    // IFLA_LINKINFO from ip link add link eth0 name eth0.10 type vlan id 10
+    let raw_data = [28u8, 0, 18, 0x80, 9, 0, 1, 0, 118, 108, 97, 110, 0, 0, 0, 0,
+                    12, 0, 2, 0x80, 6, 0, 1, 0, 10, 0, 0, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let v = read_attrs::<Ifla>(&mut cursor, raw_data.len(), Value::default()).unwrap();
+    let info = match v[0].rta_payload {
+        Payload::Nested(Nested::IflaInfo(ref info)) => info,
+        _ => panic!("expected IflaInfo attributes"),
+    };
+    match info[1].rta_payload {
+        Payload::Nested(Nested::IflaVlan(ref vlan)) => {
+            assert!(vlan[0].rta_type == Value::Known(IflaVlan::IFLA_VLAN_ID));
+            match vlan[0].rta_payload {
+                Payload::U16(n) => assert!(n == 10),
+                _ => panic!("expected a u16"),
+            }
+        }
+        _ => panic!("expected IflaVlan attributes"),
+    }
+
+    // and from ip link add v0 type veth peer name v1, where the peer is a
+    // whole ifinfomsg
+    let raw_data = [48u8, 0, 18, 0x80, 9, 0, 1, 0, 118, 101, 116, 104, 0, 0, 0, 0,
+                    32, 0, 2, 0x80, 28, 0, 1, 0,
+                    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    7, 0, 3, 0, 118, 49, 0, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let v = read_attrs::<Ifla>(&mut cursor, raw_data.len(), Value::default()).unwrap();
+    let info = match v[0].rta_payload {
+        Payload::Nested(Nested::IflaInfo(ref info)) => info,
+        _ => panic!("expected IflaInfo attributes"),
+    };
+    let peer = match info[1].rta_payload {
+        Payload::Nested(Nested::VethInfo(ref veth)) => veth[0].clone(),
+        _ => panic!("expected VethInfo attributes"),
+    };
+    assert!(peer.rta_type == Value::Known(VethInfo::VETH_INFO_PEER));
+    assert!(format!("{:?}", peer.rta_payload).contains("String(\"v1\")"));
+}
+
+#[test]
+fn test_Rtattr_read_af_spec() {
+    // IFLA_AF_SPEC with AF_INET's IFLA_INET_CONF, cut down to forwarding = 1
+    // and mc_forwarding = 0
+    let raw_data = [20u8, 0, 26, 0x80, 16, 0, 2, 0x80, 12, 0, 1, 0,
+                    1, 0, 0, 0, 0, 0, 0, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let v = read_attrs::<Ifla>(&mut cursor, raw_data.len(), Value::default()).unwrap();
+    let inet = match v[0].rta_payload {
+        Payload::Nested(Nested::AddressFamily(ref af)) => match af[0].rta_payload {
+            Payload::Nested(Nested::IflaInet(ref inet)) => inet.clone(),
+            _ => panic!("expected IflaInet attributes"),
+        },
+        _ => panic!("expected AddressFamily attributes"),
+    };
+    match inet[0].rta_payload {
+        Payload::S32s(ref conf, names) => {
+            assert!(conf[..] == [1, 0]);
+            assert!(names[0] == "forwarding");
+        }
+        _ => panic!("expected an s32 array"),
+    }
+
+    // an AF_BRIDGE message has IFLA_BRIDGE_* directly in IFLA_AF_SPEC, eg.
+    // from bridge vlan add dev eth0 vid 10 pvid untagged
+    let raw_data = [12u8, 0, 26, 0x80, 8, 0, 2, 0, 6, 0, 10, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let v = read_attrs::<Ifla>(&mut cursor, raw_data.len(),
+                               Value::Known(AddressFamily::AF_BRIDGE)).unwrap();
+    match v[0].rta_payload {
+        Payload::Nested(Nested::IflaBridge(ref br)) => {
+            assert!(br[0].rta_type == Value::Known(IflaBridge::IFLA_BRIDGE_VLAN_INFO));
+            let s = format!("{:?}", br[0].rta_payload);
+            assert!(s.contains("flags: 6") && s.contains("vid: 10"));
+        }
+        _ => panic!("expected IflaBridge attributes"),
+    }
+}
+
+#[test]
+fn test_Ifaddrmsg_read() {
+    // ip addr add 10.0.0.1/24 dev eth0 noprefixroute: the u8 ifa_flags only
+    // has IFA_F_PERMANENT, IFA_FLAGS has IFA_F_NOPREFIXROUTE too
+    let raw_data = [2u8, 24, 0x80, 0, 2, 0, 0, 0,
+                    8, 0, 1, 0, 10, 0, 0, 1,
+                    8, 0, 8, 0, 0x80, 2, 0, 0,
+                    20, 0, 6, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                    100, 0, 0, 0, 100, 0, 0, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let ifa = Ifaddrmsg::read(&mut cursor, raw_data.len()).unwrap();
+    assert!(ifa.flags() == 0x280);
+    match ifa.ifa_attr[0].rta_payload {
+        Payload::Prefix(ip, len) => {
+            assert!(ip == IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+            assert!(len == 24);
+        }
+        _ => panic!("expected a prefix"),
+    }
+    let s = ifa.to_string();
+    assert!(s.contains("rta_value: 10.0.0.1/24"));
+    assert!(s.contains("ifa_flags: 0x280 (IFA_F_PERMANENT|IFA_F_NOPREFIXROUTE)"));
+    assert!(s.contains("ifa_scope: 0 (RT_SCOPE_UNIVERSE)"));
+    assert!(s.contains("ifa_valid: forever"));
+}
+
+#[test]
+fn test_Rtmsg_read_multipath() {
+    // ip route add 10.1.2.0/24 table 1000 nexthop via 192.168.1.1 dev eth0
+    //     nexthop via 192.168.2.1 dev eth1 weight 2
+    let raw_data = [2u8, 24, 0, 0, 252, 3, 0, 1, 0, 0, 0, 0,
+                    8, 0, 15, 0, 0xE8, 3, 0, 0,
+                    8, 0, 1, 0, 10, 1, 2, 0,
+                    36, 0, 9, 0,
+                    16, 0, 0, 0, 2, 0, 0, 0, 8, 0, 5, 0, 192, 168, 1, 1,
+                    16, 0, 0, 1, 3, 0, 0, 0, 8, 0, 5, 0, 192, 168, 2, 1];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let rt = Rtmsg::read(&mut cursor, raw_data.len()).unwrap();
+    assert!(rt.table() == 1000);
+    match rt.rtm_attr[1].rta_payload {
+        Payload::Prefix(ip, len) => {
+            assert!(ip == IpAddr::V4(Ipv4Addr::new(10, 1, 2, 0)));
+            assert!(len == 24);
+        }
+        _ => panic!("expected a prefix"),
+    }
+    match rt.rtm_attr[2].rta_payload {
+        Payload::Nexthops(ref v) => {
+            assert!(v.len() == 2);
+            assert!(v[1].rtnh_ifindex == 3 && v[1].rtnh_hops == 1);
+            match v[1].rtnh_attr[0].rta_payload {
+                Payload::Ip(ip) => assert!(ip == IpAddr::V4(Ipv4Addr::new(192, 168, 2, 1))),
+                _ => panic!("expected an IP"),
+            }
+        }
+        _ => panic!("expected nexthops"),
+    }
+    assert!(rt.to_string().contains("rtm_table: 1000 (user defined)"));
+}
+
+#[test]
+fn test_Rtattr_read_encap() {
+    // ip route add 10.1.2.0/24 encap mpls 100/200 via 192.168.1.1, where the
+    // kernel puts RTA_ENCAP before RTA_ENCAP_TYPE
+    let raw_data = [16u8, 0, 22, 0x80, 12, 0, 1, 0, 0, 6, 0x40, 0, 0, 12, 0x81, 0,
+                    6, 0, 21, 0, 1, 0, 0, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let v = read_attrs::<RtmAttr>(&mut cursor, raw_data.len(),
+                                  Value::Known(AddressFamily::AF_INET)).unwrap();
+    assert!(cursor.position() as usize == raw_data.len());
+    match v[0].rta_payload {
+        Payload::Nested(Nested::MplsIptunnel(ref mpls)) => match mpls[0].rta_payload {
+            Payload::Mpls(ref labels) => assert!(labels[..] == [100 << 12, 200 << 12 | 1 << 8]),
+            _ => panic!("expected a label stack"),
+        },
+        _ => panic!("expected MplsIptunnel attributes"),
+    }
+
+    // ip -6 route add 2001:db8::/64 encap seg6 mode encap segs fc00::1 dev eth0
+    let raw_data = [6u8, 0, 21, 0, 5, 0, 0, 0,
+                    36, 0, 22, 0x80, 32, 0, 1, 0, 1, 0, 0, 0,
+                    0, 2, 4, 0, 0, 0, 0, 0,
+                    0xfc, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let v = read_attrs::<RtmAttr>(&mut cursor, raw_data.len(),
+                                  Value::Known(AddressFamily::AF_INET6)).unwrap();
+    match v[1].rta_payload {
+        Payload::Nested(Nested::Seg6Iptunnel(ref seg6)) => {
+            let s = format!("{:?}", seg6[0].rta_payload);
+            assert!(s.contains("SEG6_IPTUN_MODE_ENCAP") && s.contains("fc00::1"));
+        }
+        _ => panic!("expected Seg6Iptunnel attributes"),
+    }
+}
+
+#[test]
+fn test_Ndmsg_read() {
+    // bridge fdb add 02:00:00:00:00:01 dev eth0 master vlan 10 permanent,
+    // where the bridge is ifindex 4
+    let raw_data = [7u8, 0, 0, 0, 3, 0, 0, 0, 0x80, 0, 0x04, 0,
+                    10, 0, 2, 0, 2, 0, 0, 0, 0, 1, 0, 0,
+                    6, 0, 5, 0, 10, 0, 0, 0,
+                    8, 0, 9, 0, 4, 0, 0, 0,
+                    20, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let nd = Ndmsg::read(&mut cursor, raw_data.len()).unwrap();
+    assert!(nd.ndm_family == Value::Known(AddressFamily::AF_BRIDGE));
+    assert!(nd.ndm_attr.len() == 4);
+    match nd.ndm_attr[3].rta_payload {
+        Payload::Struct(ref s) => assert!(format!("{:?}", s).contains("ndm_refcnt: 1")),
+        _ => panic!("expected NdaCacheinfo"),
+    }
+    assert!(nd.fdb() == Some("02:00:00:00:00:01 dev 3 vlan 10 master 4 permanent".to_string()));
+}
+
+#[test]
+fn test_Tcmsg_read_options() {
+    // tc qdisc add dev eth0 parent 1:10 handle 10: fq_codel target 5ms
+    // limit 10240, with only those two options
+    let raw_data = [0u8, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0x10, 0, 0x10, 0, 1, 0, 1, 0, 0, 0,
+                    13, 0, 1, 0, 102, 113, 95, 99, 111, 100, 101, 108, 0, 0, 0, 0,
+                    20, 0, 2, 0, 8, 0, 1, 0, 0x87, 0x13, 0, 0, 8, 0, 2, 0, 0, 0x28, 0, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let tc = Tcmsg::read(&mut cursor, raw_data.len()).unwrap();
+    assert!(tc.tcm_family == Value::Known(AddressFamily::AF_UNSPEC));
+    assert!(tc.tcm_attr.len() == 2);
+    let options = match tc.tcm_attr[1].rta_payload {
+        Payload::Nested(Nested::TcaFqCodel(ref v)) => v,
+        _ => panic!("expected TcaFqCodel attributes"),
+    };
+    assert!(options[0].rta_type == Value::Known(TcaFqCodel::TCA_FQ_CODEL_TARGET));
+    assert!(format!("{:?}", options[0].rta_payload) == "U32(4999)");
+    assert!(format!("{:?}", options[1].rta_payload) == "U32(10240)");
+
+    assert!(fmt_rate(125000) == "1Mbit");
+    assert!(fmt_rate(1500) == "12Kbit");
+    assert!(fmt_rate(1234) == "9872bit");
+}
+
+#[test]
+fn test_Tcmsg_read_filter() {
+    // tc filter add dev eth0 ingress prio 10 protocol ip flower ip_proto 6
+    // dst_ip 10.0.0.1 action drop, trimmed to a few of the keys
+    let raw_data = [0u8, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 0xF2, 0xFF, 0xFF, 0xFF, 8, 0, 10, 0,
+                    11, 0, 1, 0, 102, 108, 111, 119, 101, 114, 0, 0,
+                    76, 0, 2, 0,
+                    6, 0, 8, 0, 8, 0, 0, 0,
+                    5, 0, 9, 0, 6, 0, 0, 0,
+                    8, 0, 12, 0, 10, 0, 0, 1,
+                    48, 0, 3, 0, 44, 0, 1, 0,
+                    9, 0, 1, 0, 103, 97, 99, 116, 0, 0, 0, 0,
+                    28, 0, 2, 0, 24, 0, 2, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0,
+                    1, 0, 0, 0, 1, 0, 0, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let mut tc = Tcmsg { tcm_filter: true, ..Default::default() };
+    tc.read_into(&mut cursor, raw_data.len()).unwrap();
+    assert!(tc.prio() == Some(10));
+    assert!(tc.protocol() == Some(Value::Known(EthP::ETH_P_IP)));
+    assert!(format!("{}", tc).contains("tcm_handle: 0x1,"));
+    let options = match tc.tcm_attr[1].rta_payload {
+        Payload::Nested(Nested::TcaFlower(ref v)) => v,
+        _ => panic!("expected TcaFlower attributes"),
+    };
+    assert!(format!("{:?}", options[0].rta_payload) == "Enum(2048, \"ETH_P_IP\")");
+    assert!(format!("{:?}", options[1].rta_payload) == "Enum(6, \"IPPROTO_TCP\")");
+    let act = match options[3].rta_payload {
+        Payload::Nested(Nested::Raw(ref list)) => match list[0].rta_payload {
+            Payload::Nested(Nested::TcaAct(ref act)) => act.clone(),
+            _ => panic!("expected TcaAct attributes"),
+        },
+        _ => panic!("expected a list of actions"),
+    };
+    match act[1].rta_payload {
+        Payload::Nested(Nested::TcaGact(ref gact)) => {
+            assert!(format!("{:?}", gact[0].rta_payload).contains("action: 2"));
+        }
+        _ => panic!("expected TcaGact attributes"),
+    }
+
+    assert!(fmt_tc_action(2) == "TC_ACT_SHOT");
+    assert!(fmt_tc_action(-1) == "TC_ACT_UNSPEC");
+    assert!(fmt_tc_action(0x20000005) == "TC_ACT_GOTO_CHAIN 5");
+}
+
+#[test]
+fn test_Tcmsg_read_stats() {
+    // tc class add dev eth0 parent 1: classid 1:10 htb rate 1mbit, only
+    // TCA_KIND and the xstats in TCA_STATS2
+    let raw_data = [0u8, 0, 0, 0, 2, 0, 0, 0, 0x10, 0, 1, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0,
+                    8, 0, 1, 0, 104, 116, 98, 0,
+                    28, 0, 7, 0, 24, 0, 4, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0,
+                    0xFB, 0xFF, 0xFF, 0xFF, 7, 0, 0, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let tc = Tcmsg::read(&mut cursor, raw_data.len()).unwrap();
+    assert!(tc.kind() == Some("htb"));
+    match tc.tcm_attr[1].rta_payload {
+        Payload::Nested(Nested::TcaStats(ref stats)) => {
+            let s = format!("{:?}", stats[0].rta_payload);
+            assert!(s.contains("lends: 1") && s.contains("tokens: -5"));
+        }
+        _ => panic!("expected TcaStats attributes"),
+    }
+
+    assert!(TcHandle(tc.tcm_handle).to_string() == "1:10");
+    assert!(TcHandle(tc.tcm_parent).to_string() == "ffff:ffff (root)");
+    assert!(TcHandle(0xFFFFFFF3).to_string() == "ffff:fff3 (egress)");
+    assert!(TcHandle(0xFFFFFFF1).name_for(Some("clsact")) == Some("clsact"));
+    assert!(TcHandle(0xFFFFFFF1).name_for(Some("ingress")) == Some("ingress"));
+}
+
+#[test]
+fn test_FibRuleHdr_read() {
+    // ip rule add pref 1000 from 10.0.0.0/8 fwmark 1 uidrange 1000-2000
+    //     sport 80-90 table 100
+    let raw_data = [2u8, 0, 8, 0, 100, 0, 0, 1, 0, 0, 0, 0,
+                    8, 0, 2, 0, 10, 0, 0, 0,
+                    8, 0, 6, 0, 0xE8, 3, 0, 0,
+                    8, 0, 15, 0, 100, 0, 0, 0,
+                    8, 0, 14, 0, 0xFF, 0xFF, 0xFF, 0xFF,
+                    8, 0, 10, 0, 1, 0, 0, 0,
+                    12, 0, 20, 0, 0xE8, 3, 0, 0, 0xD0, 7, 0, 0,
+                    8, 0, 23, 0, 80, 0, 90, 0];
+    let mut cursor = Cursor::new(&raw_data[..]);
+    let rule = FibRuleHdr::read(&mut cursor, raw_data.len()).unwrap();
+    assert!(rule.family == Value::Known(AddressFamily::AF_INET));
+    assert!(rule.action == Value::Known(FrAct::FR_ACT_TO_TBL));
+    assert!(rule.table() == 100);
+    match rule.fra_attr[0].rta_payload {
+        Payload::Prefix(ip, len) => {
+            assert!(ip == IpAddr::V4(Ipv4Addr::new(10, 0, 0, 0)));
+            assert!(len == 8);
+        }
+        _ => panic!("expected FRA_SRC to be a prefix"),
+    }
+    match rule.fra_attr[3].rta_payload {
+        Payload::S32(n) => assert!(n == -1),
+        _ => panic!("expected FRA_SUPPRESS_PREFIXLEN to be an s32"),
+    }
+    assert!(rule.rule() ==
+            "1000: from 10.0.0.0/8 fwmark 0x1 uidrange 1000-2000 sport 80-90 lookup 100");
+}
@@ -0,0 +1,1045 @@
+//! Decoding with the netlink specs that ship with the kernel, in
+//! Documentation/netlink/specs. Each one describes a family: its messages,
+//! their fixed headers, the attribute sets in them and the enums and flags
+//! that the values come from. A message that a loaded spec covers is decoded
+//! from the spec instead of the enums that build.rs generated.
+
+use ::std::cell::RefCell;
+use ::std::collections::HashMap;
+use ::std::fmt;
+use ::std::fs::{self, File};
+use ::std::io::{self, Cursor, Read};
+use ::std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use ::std::path::Path;
+use ::byteorder::{BigEndian, ByteOrder, NativeEndian};
+use ::yaml::{self, Yaml};
+use nl::{format_indent, netlink, NlMsg, NlMsgEnum, NlMsgFlags, Pretty, Value, Error, ErrorKind};
+use nl::error;
+use nl::{check_len, read_u8, read_u16, read_bytes};
+use nl::rtnetlink::{NLA_F_NESTED, NLA_F_NET_BYTEORDER, NLA_TYPE_MASK};
+
+/// nlmsg_types below this are NLMSG_NOOP, NLMSG_ERROR and so on
+const NLMSG_MIN_TYPE: u16 = 16;
+/// The genetlink family that tells you the ids of the others
+const GENL_ID_CTRL: u16 = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Protocol {
+    Genetlink,
+    NetlinkRaw,
+}
+
+/// An enum or flags definition. The value of flags is the bit, not its
+/// position.
+#[derive(Debug, Clone, Default)]
+struct EnumDef {
+    flags: bool,
+    entries: Vec<(String, u64)>,
+}
+impl EnumDef {
+    fn name_of(&self, n: u64) -> Option<&str> {
+        self.entries.iter().find(|&&(_, v)| v == n).map(|&(ref name, _)| &name[..])
+    }
+    fn flag_names(&self, n: u64) -> String {
+        let mut names = Vec::new();
+        for shift in 0..64 {
+            let bit = n & (1 << shift);
+            if bit != 0 {
+                names.push(match self.name_of(bit) {
+                    Some(name) => name.to_string(),
+                    None => format!("{:#X}", bit),
+                });
+            }
+        }
+        names.join("|")
+    }
+}
+
+/// An attribute, or a member of a struct
+#[derive(Debug, Clone, Default)]
+struct AttrDef {
+    name: String,
+    value: u16,
+    typ: String,
+    big_endian: bool,
+    len: Option<usize>,
+    enum_name: Option<String>,
+    enum_as_flags: bool,
+    display_hint: Option<String>,
+    nested: Option<String>,
+    sub_type: Option<String>,
+    struct_name: Option<String>,
+    sub_message: Option<String>,
+    selector: Option<String>,
+}
+impl AttrDef {
+    fn from_yaml(y: &Yaml, value: u16) -> AttrDef {
+        let s = |k: &str| y.str_of(k).map(|s| s.to_string());
+        AttrDef {
+            name: s("name").unwrap_or_default(),
+            value: value,
+            typ: s("type").unwrap_or_default(),
+            big_endian: y.str_of("byte-order") == Some("big-endian"),
+            len: y.get("len").and_then(|l| l.as_i64()).map(|l| l as usize),
+            enum_name: s("enum"),
+            enum_as_flags: y.str_of("enum-as-flags") == Some("true"),
+            display_hint: s("display-hint"),
+            nested: s("nested-attributes"),
+            sub_type: s("sub-type"),
+            struct_name: s("struct"),
+            sub_message: s("sub-message"),
+            selector: s("selector"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SubMessageFormat {
+    value: String,
+    attr_set: Option<String>,
+    fixed_header: Option<String>,
+}
+
+/// What a message is, by its nlmsg_type (or genetlink cmd)
+#[derive(Debug, Clone)]
+struct MsgDef {
+    value: u16,
+    op: String,
+    attr_set: Option<String>,
+    fixed_header: Option<String>,
+}
+
+/// One spec, which is one netlink family, or part of NETLINK_ROUTE
+#[derive(Debug, Clone)]
+pub struct Family {
+    pub name: String,
+    protocol: Protocol,
+    protonum: u16,
+    enums: HashMap<String, EnumDef>,
+    structs: HashMap<String, Vec<AttrDef>>,
+    attr_sets: HashMap<String, Vec<AttrDef>>,
+    sub_messages: HashMap<String, Vec<SubMessageFormat>>,
+    // the directional enum-model numbers requests and replies separately,
+    // so a value can be one op on the way in and another on the way out.
+    // Notifications count as replies.
+    requests: Vec<MsgDef>,
+    replies: Vec<MsgDef>,
+}
+impl Family {
+    fn from_yaml(y: &Yaml) -> Result<Family, String> {
+        let name = match y.str_of("name") {
+            Some(n) => n.to_string(),
+            None => return Err("no name".to_string()),
+        };
+        let protocol = match y.str_of("protocol") {
+            Some("netlink-raw") => Protocol::NetlinkRaw,
+            // genetlink, genetlink-c and genetlink-legacy
+            _ => Protocol::Genetlink,
+        };
+        let protonum = match protocol {
+            Protocol::NetlinkRaw => match y.get("protonum").and_then(|p| p.as_i64()) {
+                Some(p) => p as u16,
+                None => return Err("netlink-raw without a protonum".to_string()),
+            },
+            Protocol::Genetlink => netlink::NetlinkFamily::NETLINK_GENERIC as u16,
+        };
+        let mut f = Family {
+            name: name,
+            protocol: protocol,
+            protonum: protonum,
+            enums: HashMap::new(),
+            structs: HashMap::new(),
+            attr_sets: HashMap::new(),
+            sub_messages: HashMap::new(),
+            requests: Vec::new(),
+            replies: Vec::new(),
+        };
+
+        for d in y.get("definitions").map_or(&[][..], |d| d.as_seq()) {
+            let name = match d.str_of("name") {
+                Some(n) => n.to_string(),
+                None => continue,
+            };
+            match d.str_of("type") {
+                Some("enum") | Some("flags") => {
+                    let mut e = EnumDef { flags: d.str_of("type") == Some("flags"),
+                                          entries: Vec::new() };
+                    let mut next = d.get("value-start").and_then(|v| v.as_i64()).unwrap_or(0);
+                    for entry in d.get("entries").map_or(&[][..], |e| e.as_seq()) {
+                        let entry_name = match entry.as_str().or(entry.str_of("name")) {
+                            Some(n) => n.to_string(),
+                            None => continue,
+                        };
+                        let v = entry.get("value").and_then(|v| v.as_i64()).unwrap_or(next);
+                        next = v.saturating_add(1);
+                        // flags are bit numbers, which have to fit in a u64
+                        let v = if !e.flags {
+                            v as u64
+                        } else {
+                            let bit = if v >= 0 && v <= u32::max_value() as i64 {
+                                1u64.checked_shl(v as u32)
+                            } else {
+                                None
+                            };
+                            match bit {
+                                Some(bit) => bit,
+                                None => {
+                                    warn!("{}: flag {} is bit {}, skipping it", name, entry_name, v);
+                                    continue;
+                                }
+                            }
+                        };
+                        e.entries.push((entry_name, v));
+                    }
+                    f.enums.insert(name, e);
+                }
+                Some("struct") => {
+                    let members = d.get("members").map_or(&[][..], |m| m.as_seq());
+                    f.structs.insert(name, members.iter().map(|m| AttrDef::from_yaml(m, 0))
+                                                          .collect());
+                }
+                _ => (),
+            }
+        }
+
+        let mut subsets = Vec::new();
+        for s in y.get("attribute-sets").map_or(&[][..], |s| s.as_seq()) {
+            let name = match s.str_of("name") {
+                Some(n) => n.to_string(),
+                None => continue,
+            };
+            let mut attrs = Vec::new();
+            // attributes are numbered from 1, 0 is left for *_UNSPEC
+            let mut next = 1;
+            for a in s.get("attributes").map_or(&[][..], |a| a.as_seq()) {
+                let v = a.get("value").and_then(|v| v.as_i64()).unwrap_or(next);
+                next = v.saturating_add(1);
+                if v < 0 || v > u16::max_value() as i64 {
+                    warn!("{}: attribute {} is {}, skipping it", name,
+                          a.str_of("name").unwrap_or("?"), v);
+                    continue;
+                }
+                attrs.push(AttrDef::from_yaml(a, v as u16));
+            }
+            if let Some(parent) = s.str_of("subset-of") {
+                subsets.push((name.clone(), parent.to_string()));
+            }
+            f.attr_sets.insert(name, attrs);
+        }
+        // a subset only names the attributes, the rest is in the set it is a
+        // subset of
+        for (name, parent) in subsets {
+            let parent = match f.attr_sets.get(&parent) {
+                Some(p) => p.clone(),
+                None => continue,
+            };
+            if let Some(attrs) = f.attr_sets.get_mut(&name) {
+                for a in attrs.iter_mut() {
+                    if let Some(p) = parent.iter().find(|p| p.name == a.name) {
+                        *a = p.clone();
+                    }
+                }
+            }
+        }
+
+        for s in y.get("sub-messages").map_or(&[][..], |s| s.as_seq()) {
+            let name = match s.str_of("name") {
+                Some(n) => n.to_string(),
+                None => continue,
+            };
+            let formats = s.get("formats").map_or(&[][..], |f| f.as_seq()).iter().map(|fmt| {
+                SubMessageFormat {
+                    value: fmt.str_of("value").unwrap_or_default().to_string(),
+                    attr_set: fmt.str_of("attribute-set").map(|s| s.to_string()),
+                    fixed_header: fmt.str_of("fixed-header").map(|s| s.to_string()),
+                }
+            }).collect();
+            f.sub_messages.insert(name, formats);
+        }
+
+        if let Some(ops) = y.get("operations") {
+            let directional = ops.str_of("enum-model") == Some("directional");
+            let fixed_header = ops.str_of("fixed-header");
+            let mut next = 1;
+            for op in ops.get("list").map_or(&[][..], |l| l.as_seq()) {
+                let mut requests = Vec::new();
+                let mut replies = Vec::new();
+                if let Some(v) = op.get("value").and_then(|v| v.as_i64()) {
+                    // a directional op only has a value of its own when it
+                    // is a notification
+                    let notify = op.get("notify").is_some() || op.get("event").is_some();
+                    if !directional || !notify {
+                        requests.push(v as u16);
+                    }
+                    replies.push(v as u16);
+                    next = v.saturating_add(1);
+                }
+                for kind in &["do", "dump", "event"] {
+                    let value = |dir| op.get(kind).and_then(|k| k.get(dir))
+                                        .and_then(|d| d.get("value")).and_then(|v| v.as_i64())
+                                        .map(|v| v as u16);
+                    if let Some(v) = value("request") {
+                        if !requests.contains(&v) {
+                            requests.push(v);
+                        }
+                    }
+                    if let Some(v) = value("reply") {
+                        if !replies.contains(&v) {
+                            replies.push(v);
+                        }
+                    }
+                }
+                if requests.is_empty() && replies.is_empty() && !directional {
+                    requests.push(next as u16);
+                    replies.push(next as u16);
+                    next += 1;
+                }
+                let def = |v| MsgDef {
+                    value: v,
+                    op: op.str_of("name").unwrap_or_default().to_string(),
+                    attr_set: op.str_of("attribute-set").map(|s| s.to_string()),
+                    fixed_header: op.str_of("fixed-header").or(fixed_header)
+                                    .map(|s| s.to_string()),
+                };
+                f.requests.extend(requests.into_iter().map(&def));
+                f.replies.extend(replies.into_iter().map(&def));
+            }
+        }
+        Ok(f)
+    }
+    fn msg(&self, value: u16, request: bool) -> Option<&MsgDef> {
+        let msgs = if request { &self.requests } else { &self.replies };
+        msgs.iter().find(|m| m.value == value)
+    }
+    fn read_struct(&self, name: &str, cursor: &mut Cursor<&[u8]>, end: usize,
+                   out: &mut Vec<SpecAttr>) -> error::Result<()> {
+        let members = match self.structs.get(name) {
+            Some(m) => m,
+            None => return Ok(()),
+        };
+        for m in members.iter() {
+            let len = match int_size(&m.typ) {
+                Some(len) => len,
+                None => m.len.unwrap_or(0),
+            };
+            let data = try!(read_bytes(cursor, end, len, "fixed header"));
+            if m.typ == "pad" {
+                continue;
+            }
+            let value = if m.typ == "binary" {
+                self.binary_value(m, &data)
+            }
+            else {
+                self.int_value(m, &data, m.big_endian)
+            };
+            out.push(SpecAttr { name: m.name.clone(), value: value });
+        }
+        Ok(())
+    }
+    fn int_value(&self, d: &AttrDef, data: &[u8], big_endian: bool) -> SpecValue {
+        let (n, signed) = match read_int(&d.typ, data, big_endian) {
+            Some(n) => n,
+            None => return SpecValue::Bytes(data.to_vec()),
+        };
+        if signed {
+            return SpecValue::Signed(n as i64);
+        }
+        if let Some(e) = d.enum_name.as_ref().and_then(|e| self.enums.get(e)) {
+            if e.flags || d.enum_as_flags {
+                return SpecValue::Flags(n, e.flag_names(n));
+            }
+            return match e.name_of(n) {
+                Some(name) => SpecValue::Enum(n, name.to_string()),
+                None => SpecValue::Enum(n, format!("Unknown({})", n)),
+            };
+        }
+        match d.display_hint.as_ref().map(|h| &h[..]) {
+            Some("hex") => SpecValue::Hex(n),
+            Some("ipv4") if data.len() == 4 => SpecValue::Ip(ip(data)),
+            _ => SpecValue::Unsigned(n),
+        }
+    }
+    fn binary_value(&self, d: &AttrDef, data: &[u8]) -> SpecValue {
+        if let Some(ref s) = d.struct_name {
+            let mut fields = Vec::new();
+            let mut cursor = Cursor::new(data);
+            // a struct that is cut short keeps what there was of it
+            let _ = self.read_struct(s, &mut cursor, data.len(), &mut fields);
+            return SpecValue::Struct(fields);
+        }
+        match (d.display_hint.as_ref().map(|h| &h[..]), data.len()) {
+            (Some("mac"), n) if n > 0 => SpecValue::Mac(data.to_vec()),
+            (Some("ipv4"), 4) | (Some("ipv6"), 16) |
+            (Some("ipv4-or-v6"), 4) | (Some("ipv4-or-v6"), 16) => SpecValue::Ip(ip(data)),
+            _ => SpecValue::Bytes(data.to_vec()),
+        }
+    }
+    /// Read the attributes in `set` up to `end`
+    fn read_attrs(&self, set: Option<&str>, cursor: &mut Cursor<&[u8]>, end: usize,
+                  out: &mut Vec<SpecAttr>) -> error::Result<()> {
+        let defs = set.and_then(|s| self.attr_sets.get(s));
+        while (cursor.position() as usize) < end {
+            let (t, flags, data, attr_end) = try!(read_rtattr(cursor, end));
+            let def = defs.and_then(|d| d.iter().find(|a| a.value == t));
+            let name = match def {
+                Some(d) => d.name.clone(),
+                None => format!("Unknown({})", t),
+            };
+            let value = match def {
+                Some(d) => try!(self.attr_value(d, cursor, &data, flags, attr_end, out)),
+                None if flags & NLA_F_NESTED != 0 => {
+                    let mut v = Vec::new();
+                    try!(self.read_attrs(None, cursor, attr_end, &mut v));
+                    SpecValue::Nested(v)
+                }
+                None => SpecValue::Bytes(data),
+            };
+            out.push(SpecAttr { name: name, value: value });
+            cursor.set_position(attr_end as u64);
+            NlMsg::nlmsg_align(cursor);
+        }
+        Ok(())
+    }
+    /// The value of an attribute, whose payload is `data` and starts at the
+    /// cursor. `siblings` are the attributes before it in the same set.
+    fn attr_value(&self, d: &AttrDef, cursor: &mut Cursor<&[u8]>, data: &[u8], flags: u16,
+                  end: usize, siblings: &[SpecAttr]) -> error::Result<SpecValue> {
+        let v = match &d.typ[..] {
+            "flag" => SpecValue::Flag,
+            "string" => {
+                let s = match data.iter().position(|b| *b == 0) {
+                    Some(nul) => &data[..nul],
+                    None => data,
+                };
+                SpecValue::String(String::from_utf8_lossy(s).into_owned())
+            }
+            "binary" => self.binary_value(d, data),
+            "nest" => {
+                let mut v = Vec::new();
+                try!(self.read_attrs(d.nested.as_ref().map(|n| &n[..]), cursor, end, &mut v));
+                SpecValue::Nested(v)
+            }
+            "indexed-array" | "nest-type-value" => {
+                // the types of the attributes inside are just their index
+                let mut v = Vec::new();
+                while (cursor.position() as usize) < end {
+                    let (t, flags, data, attr_end) = try!(read_rtattr(cursor, end));
+                    let mut item = d.clone();
+                    item.typ = d.sub_type.clone().unwrap_or("nest".to_string());
+                    let value = try!(self.attr_value(&item, cursor, &data, flags, attr_end, &[]));
+                    v.push(SpecAttr { name: format!("[{}]", t), value: value });
+                    cursor.set_position(attr_end as u64);
+                    NlMsg::nlmsg_align(cursor);
+                }
+                SpecValue::Nested(v)
+            }
+            "sub-message" => {
+                // which format it is depends on another attribute, eg. the
+                // kind of link for the data in IFLA_LINKINFO
+                let selected = d.selector.as_ref().and_then(|sel| {
+                    siblings.iter().find(|s| s.name == *sel)
+                }).and_then(|s| match s.value {
+                    SpecValue::String(ref s) => Some(s.clone()),
+                    _ => None,
+                });
+                let format = d.sub_message.as_ref().and_then(|m| self.sub_messages.get(m))
+                              .and_then(|formats| {
+                                  formats.iter().find(|f| Some(&f.value) == selected.as_ref())
+                              });
+                match format {
+                    Some(format) => {
+                        let mut v = Vec::new();
+                        if let Some(ref h) = format.fixed_header {
+                            try!(self.read_struct(h, cursor, end, &mut v));
+                        }
+                        if format.attr_set.is_some() {
+                            try!(self.read_attrs(format.attr_set.as_ref().map(|s| &s[..]),
+                                                 cursor, end, &mut v));
+                        }
+                        SpecValue::Nested(v)
+                    }
+                    None => SpecValue::Bytes(data.to_vec()),
+                }
+            }
+            "bitfield32" if data.len() == 8 => {
+                SpecValue::Struct(vec![
+                    SpecAttr { name: "value".to_string(),
+                               value: SpecValue::Hex(NativeEndian::read_u32(data) as u64) },
+                    SpecAttr { name: "selector".to_string(),
+                               value: SpecValue::Hex(NativeEndian::read_u32(&data[4..]) as u64) },
+                ])
+            }
+            t if int_size(t).is_some() || t == "uint" || t == "sint" => {
+                self.int_value(d, data, d.big_endian || flags & NLA_F_NET_BYTEORDER != 0)
+            }
+            _ => SpecValue::Bytes(data.to_vec()),
+        };
+        Ok(v)
+    }
+}
+
+fn int_size(typ: &str) -> Option<usize> {
+    match typ {
+        "u8" | "s8" => Some(1),
+        "u16" | "s16" => Some(2),
+        "u32" | "s32" => Some(4),
+        "u64" | "s64" => Some(8),
+        _ => None,
+    }
+}
+
+/// The integer in `data`, and whether it is signed. uint and sint are
+/// 4 or 8 bytes.
+fn read_int(typ: &str, data: &[u8], big_endian: bool) -> Option<(u64, bool)> {
+    let signed = typ.starts_with('s');
+    let len = match typ {
+        "uint" | "sint" if data.len() == 4 || data.len() == 8 => data.len(),
+        _ => match int_size(typ) {
+            Some(len) if len == data.len() => len,
+            _ => return None,
+        },
+    };
+    let n = match (len, big_endian) {
+        (1, _) => data[0] as u64,
+        (2, true) => BigEndian::read_u16(data) as u64,
+        (2, false) => NativeEndian::read_u16(data) as u64,
+        (4, true) => BigEndian::read_u32(data) as u64,
+        (4, false) => NativeEndian::read_u32(data) as u64,
+        (8, true) => BigEndian::read_u64(data),
+        (_, _) => NativeEndian::read_u64(data),
+    };
+    if signed {
+        // sign extend
+        let shift = 64 - len * 8;
+        return Some((((n << shift) as i64 >> shift) as u64, true));
+    }
+    Some((n, false))
+}
+
+fn ip(data: &[u8]) -> IpAddr {
+    if data.len() == 4 {
+        return IpAddr::V4(Ipv4Addr::new(data[0], data[1], data[2], data[3]));
+    }
+    let mut a = [0u16; 8];
+    for i in 0..8 {
+        a[i] = BigEndian::read_u16(&data[i*2..]);
+    }
+    IpAddr::V6(Ipv6Addr::new(a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7]))
+}
+
+/// Read an rtattr header and its payload. Returns the type and flags, the
+/// payload, and where the attribute ends. The cursor is left at the start
+/// of the payload.
+fn read_rtattr(cursor: &mut Cursor<&[u8]>, end: usize) -> error::Result<(u16, u16, Vec<u8>, usize)> {
+    let start = cursor.position() as usize;
+    let rta_len = try!(read_u16::<NativeEndian>(cursor, end, "rtattr"));
+    let rta_type = try!(read_u16::<NativeEndian>(cursor, end, "rtattr"));
+    if rta_len < 4 || start + rta_len as usize > end {
+        return Err(Error::new(ErrorKind::BadRtaLen { rta_len: rta_len, available: end - start },
+                              start));
+    }
+    let data = try!(read_bytes(cursor, end, rta_len as usize - 4, "rta_value"));
+    cursor.set_position((start + 4) as u64);
+    Ok((rta_type & NLA_TYPE_MASK, rta_type & !NLA_TYPE_MASK, data, start + rta_len as usize))
+}
+
+/// A value from a fixed header or an attribute
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecValue {
+    Unsigned(u64),
+    Signed(i64),
+    Hex(u64),
+    /// A number and the name that its enum has for it
+    Enum(u64, String),
+    /// A number and the names of the flags that are set in it
+    Flags(u64, String),
+    String(String),
+    Bytes(Vec<u8>),
+    Mac(Vec<u8>),
+    Ip(IpAddr),
+    Flag,
+    Nested(Vec<SpecAttr>),
+    Struct(Vec<SpecAttr>),
+}
+impl Pretty for SpecValue {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        match *self {
+            SpecValue::Unsigned(n) => write!(f, "{}", n),
+            SpecValue::Signed(n) => write!(f, "{}", n),
+            SpecValue::Hex(n) => write!(f, "{:#X}", n),
+            SpecValue::Enum(_, ref name) => write!(f, "{}", name),
+            SpecValue::Flags(n, ref names) => write!(f, "{:#X} ({})", n, names),
+            SpecValue::String(ref s) => write!(f, "{:?}", s),
+            SpecValue::Bytes(ref b) => {
+                try!(write!(f, "["));
+                let mut count: usize = 1;
+                for a in b.iter() {
+                    try!(write!(f, " {:#X}", a));
+                    if count < b.len() {
+                        try!(write!(f, ","));
+                    }
+                    count = count + 1;
+                }
+                write!(f, " ]")
+            }
+            SpecValue::Mac(ref m) => {
+                for (i, b) in m.iter().enumerate() {
+                    try!(write!(f, "{}{:02x}", if i == 0 { "" } else { ":" }, b));
+                }
+                Ok(())
+            }
+            SpecValue::Ip(ref ip) => write!(f, "{}", ip),
+            SpecValue::Flag => write!(f, "true"),
+            SpecValue::Nested(ref v) => {
+                let i_s_p = format_indent(indent+1);
+                try!(write!(f, "[ "));
+                let mut count: usize = 1;
+                for a in v.iter() {
+                    try!(a.pretty_fmt(f, indent+1));
+                    if count < v.len() {
+                        try!(write!(f, ",\n{}", i_s_p));
+                    }
+                    count = count + 1;
+                }
+                write!(f, " ]")
+            }
+            SpecValue::Struct(ref v) => {
+                try!(write!(f, "{{\n"));
+                for a in v.iter() {
+                    try!(write!(f, "{}    {}: ", i_s, a.name));
+                    try!(a.value.pretty_fmt(f, indent+1));
+                    try!(write!(f, ",\n"));
+                }
+                write!(f, "{}}}", i_s)
+            }
+        }
+    }
+}
+
+/// An attribute, or a field of a fixed header, with the name from the spec
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecAttr {
+    pub name: String,
+    pub value: SpecValue,
+}
+impl SpecAttr {
+    fn find<'a>(attrs: &'a [SpecAttr], name: &str) -> Option<&'a SpecValue> {
+        attrs.iter().find(|a| a.name == name).map(|a| &a.value)
+    }
+}
+impl Pretty for SpecAttr {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    type: {},\n", i_s, self.name));
+        try!(write!(f, "{}    value: ", i_s));
+        try!(self.value.pretty_fmt(f, indent+1));
+        write!(f, ",\n{}}}", i_s)
+    }
+}
+
+/// A message decoded with a spec
+#[derive(Debug, Clone, Default)]
+pub struct SpecMsg {
+    pub family: String,
+    pub op: String,
+    /// The genlmsghdr for genetlink, then the fixed header, if there is one
+    pub header: Vec<SpecAttr>,
+    pub attrs: Vec<SpecAttr>,
+}
+impl SpecMsg {
+    /// The interface that this message is about, if it has one
+    pub fn ifindex(&self) -> Option<i32> {
+        let index = self.header.iter().find(|h| h.name.ends_with("index")).map(|h| &h.value)
+                        .or(SpecAttr::find(&self.attrs, "ifindex"))
+                        .or(SpecAttr::find(&self.attrs, "oif"));
+        match index {
+            Some(&SpecValue::Unsigned(n)) => Some(n as i32),
+            Some(&SpecValue::Signed(n)) => Some(n as i32),
+            _ => None,
+        }
+    }
+}
+impl Pretty for SpecMsg {
+    fn pretty_fmt(&self, f: &mut fmt::Formatter, indent: i32) -> fmt::Result {
+        let i_s = format_indent(indent);
+        try!(write!(f, "{{\n"));
+        try!(write!(f, "{}    family: {},\n", i_s, self.family));
+        try!(write!(f, "{}    op: {},\n", i_s, self.op));
+        for h in self.header.iter() {
+            try!(write!(f, "{}    {}: ", i_s, h.name));
+            try!(h.value.pretty_fmt(f, indent+1));
+            try!(write!(f, ",\n"));
+        }
+        try!(write!(f, "{}    attrs: ", i_s));
+        try!(SpecValue::Nested(self.attrs.clone()).pretty_fmt(f, indent));
+        write!(f, ",\n{}}}", i_s)
+    }
+}
+
+/// The specs from a directory
+#[derive(Debug, Default)]
+pub struct SpecSet {
+    families: Vec<Family>,
+    /// genetlink family ids are handed out at runtime, so we learn them
+    /// from the nlctrl messages that go by
+    genl_ids: RefCell<HashMap<u16, usize>>,
+}
+impl SpecSet {
+    pub fn new() -> SpecSet {
+        SpecSet::default()
+    }
+    /// Load every .yaml file in `dir`. Specs that we can't make sense of are
+    /// skipped with a warning.
+    pub fn load_dir(dir: &Path) -> io::Result<SpecSet> {
+        let mut specs = SpecSet::new();
+        let mut paths: Vec<_> = try!(fs::read_dir(dir)).filter_map(|e| e.ok())
+                                                       .map(|e| e.path())
+                                                       .filter(|p| p.extension().map_or(false, |e| e == "yaml"))
+                                                       .collect();
+        paths.sort();
+        for path in paths {
+            let mut s = String::new();
+            try!(try!(File::open(&path)).read_to_string(&mut s));
+            if let Err(e) = specs.add(&s) {
+                warn!("skipping {}: {}", path.display(), e);
+            }
+        }
+        Ok(specs)
+    }
+    /// Add the spec in `s`
+    pub fn add(&mut self, s: &str) -> Result<(), String> {
+        let y = try!(yaml::parse(s).map_err(|e| e.to_string()));
+        let f = try!(Family::from_yaml(&y));
+        info!("loaded the {} spec, {} requests and {} replies", f.name, f.requests.len(),
+              f.replies.len());
+        self.families.push(f);
+        Ok(())
+    }
+    pub fn families(&self) -> &[Family] {
+        &self.families
+    }
+    fn find(&self, family: Value<netlink::NetlinkFamily>, nlmsg_type: u16,
+            request: bool) -> Option<&Family> {
+        let protonum = match family {
+            Value::Known(f) => f as u16,
+            Value::Unknown(n) => n as u16,
+        };
+        if protonum == netlink::NetlinkFamily::NETLINK_GENERIC as u16 {
+            if nlmsg_type == GENL_ID_CTRL {
+                return self.families.iter().find(|f| f.name == "nlctrl");
+            }
+            return self.genl_ids.borrow().get(&nlmsg_type).map(|i| &self.families[*i]);
+        }
+        self.families.iter().find(|f| {
+            f.protocol == Protocol::NetlinkRaw && f.protonum == protonum &&
+            f.msg(nlmsg_type, request).is_some()
+        })
+    }
+    /// Decode the body of a message that ends at `end`, if a spec covers it.
+    /// Otherwise the cursor is left where it is and we return None. The
+    /// nlmsg_flags say whether to look it up as a request or a reply.
+    pub fn read(&self, cursor: &mut Cursor<&[u8]>, family: Value<netlink::NetlinkFamily>,
+                nlmsg_type: u16, nlmsg_flags: u16, end: usize) -> Option<NlMsgEnum> {
+        if nlmsg_type < NLMSG_MIN_TYPE {
+            return None;
+        }
+        let request = nlmsg_flags & NlMsgFlags::NLM_F_REQUEST as u16 != 0;
+        let f = match self.find(family, nlmsg_type, request) {
+            Some(f) => f,
+            None => return None,
+        };
+        let mut msg = SpecMsg { family: f.name.clone(), ..SpecMsg::default() };
+        let r = self.read_body(f, &mut msg, cursor, nlmsg_type, request, end);
+        if f.name == "nlctrl" {
+            self.learn_genl_id(&msg);
+        }
+        Some(match r {
+            Ok(()) => NlMsgEnum::Spec(msg),
+            Err(e) => NlMsgEnum::Malformed(Box::new(NlMsgEnum::Spec(msg)), e),
+        })
+    }
+    fn read_body(&self, f: &Family, msg: &mut SpecMsg, cursor: &mut Cursor<&[u8]>,
+                 nlmsg_type: u16, request: bool, end: usize) -> error::Result<()> {
+        let value = match f.protocol {
+            Protocol::Genetlink => {
+                try!(check_len(cursor, end, 4, "genlmsghdr"));
+                let cmd = try!(read_u8(cursor, end, "genlmsghdr"));
+                let version = try!(read_u8(cursor, end, "genlmsghdr"));
+                try!(read_u16::<NativeEndian>(cursor, end, "genlmsghdr"));
+                msg.header.push(SpecAttr { name: "cmd".to_string(),
+                                           value: SpecValue::Unsigned(cmd as u64) });
+                msg.header.push(SpecAttr { name: "version".to_string(),
+                                           value: SpecValue::Unsigned(version as u64) });
+                cmd as u16
+            }
+            Protocol::NetlinkRaw => nlmsg_type,
+        };
+        let def = match f.msg(value, request) {
+            Some(d) => d,
+            None => {
+                msg.op = format!("Unknown({})", value);
+                cursor.set_position(end as u64);
+                return Ok(());
+            }
+        };
+        msg.op = def.op.clone();
+        if let Some(ref h) = def.fixed_header {
+            try!(f.read_struct(h, cursor, end, &mut msg.header));
+        }
+        f.read_attrs(def.attr_set.as_ref().map(|s| &s[..]), cursor, end, &mut msg.attrs)
+    }
+    fn learn_genl_id(&self, msg: &SpecMsg) {
+        let id = match SpecAttr::find(&msg.attrs, "family-id") {
+            Some(&SpecValue::Unsigned(id)) => id as u16,
+            _ => return,
+        };
+        let name = match SpecAttr::find(&msg.attrs, "family-name") {
+            Some(&SpecValue::String(ref name)) => name,
+            _ => return,
+        };
+        if let Some(i) = self.families.iter().position(|f| f.name == *name) {
+            debug!("genetlink family {} is {}", name, id);
+            self.genl_ids.borrow_mut().insert(id, i);
+        }
+    }
+}
+
+#[cfg(test)]
+const TEST_SPEC: &'static str = "
+name: rt-link
+protocol: netlink-raw
+protonum: 0
+definitions:
+  -
+    name: ifinfo-flags
+    type: flags
+    entries: [ up, broadcast ]
+  -
+    name: ifinfomsg
+    type: struct
+    members:
+      -
+        name: ifi-family
+        type: u8
+      -
+        name: pad
+        type: pad
+        len: 1
+      -
+        name: ifi-type
+        type: u16
+      -
+        name: ifi-index
+        type: s32
+      -
+        name: ifi-flags
+        type: u32
+        enum: ifinfo-flags
+        enum-as-flags: true
+      -
+        name: ifi-change
+        type: u32
+attribute-sets:
+  -
+    name: link-attrs
+    attributes:
+      -
+        name: address
+        type: binary
+        display-hint: mac
+      -
+        name: broadcast
+        type: binary
+        display-hint: mac
+      -
+        name: ifname
+        type: string
+      -
+        name: mtu
+        type: u32
+      -
+        name: linkinfo
+        value: 18
+        type: nest
+        nested-attributes: linkinfo-attrs
+  -
+    name: linkinfo-attrs
+    attributes:
+      -
+        name: kind
+        type: string
+      -
+        name: data
+        type: sub-message
+        sub-message: linkinfo-data-msg
+        selector: kind
+  -
+    name: linkinfo-vlan-attrs
+    attributes:
+      -
+        name: id
+        type: u16
+sub-messages:
+  -
+    name: linkinfo-data-msg
+    formats:
+      -
+        value: vlan
+        attribute-set: linkinfo-vlan-attrs
+operations:
+  fixed-header: ifinfomsg
+  enum-model: directional
+  list:
+    -
+      name: newlink
+      attribute-set: link-attrs
+      do:
+        request:
+          value: 16
+    -
+      name: getlink
+      attribute-set: link-attrs
+      do:
+        request:
+          value: 18
+        reply:
+          value: 16
+";
+
+#[test]
+fn test_SpecSet_read() {
+    let mut specs = SpecSet::new();
+    specs.add(TEST_SPEC).unwrap();
+    assert!(specs.families().len() == 1);
+
+    // RTM_NEWLINK for vlan10, index 5, up, mtu 1500, a vlan with id 10
+    let raw_data = [92u8, 0, 0, 0, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    0, 0, 1, 0, 5, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0,
+                    11, 0, 3, 0, 118, 108, 97, 110, 49, 48, 0, 0,
+                    8, 0, 4, 0, 220, 5, 0, 0,
+                    10, 0, 1, 0, 2, 0, 0, 0, 0, 1, 0, 0,
+                    28, 0, 18, 0x80, 9, 0, 1, 0, 118, 108, 97, 110, 0, 0, 0, 0,
+                    12, 0, 2, 0x80, 6, 0, 1, 0, 10, 0, 0, 0];
+    let raw_data = &raw_data[..];
+    let mut cursor = Cursor::new(raw_data);
+    cursor.set_position(16);
+    let family = Value::Known(netlink::NetlinkFamily::NETLINK_ROUTE);
+    let request = NlMsgFlags::NLM_F_REQUEST as u16;
+    let m = match specs.read(&mut cursor, family, 16, request, raw_data.len()) {
+        Some(NlMsgEnum::Spec(m)) => m,
+        m => panic!("expected a Spec message, not {:?}", m),
+    };
+    assert!(m.family == "rt-link");
+    assert!(m.op == "newlink");
+    assert!(m.ifindex() == Some(5));
+    assert!(SpecAttr::find(&m.header, "ifi-flags") ==
+            Some(&SpecValue::Flags(3, "up|broadcast".to_string())));
+    assert!(SpecAttr::find(&m.attrs, "ifname") == Some(&SpecValue::String("vlan10".to_string())));
+    assert!(SpecAttr::find(&m.attrs, "mtu") == Some(&SpecValue::Unsigned(1500)));
+    assert!(SpecAttr::find(&m.attrs, "address") ==
+            Some(&SpecValue::Mac(vec![2, 0, 0, 0, 0, 1])));
+    let linkinfo = match SpecAttr::find(&m.attrs, "linkinfo") {
+        Some(&SpecValue::Nested(ref v)) => v.clone(),
+        v => panic!("expected linkinfo to be nested, not {:?}", v),
+    };
+    assert!(SpecAttr::find(&linkinfo, "data") ==
+            Some(&SpecValue::Nested(vec![SpecAttr { name: "id".to_string(),
+                                                    value: SpecValue::Unsigned(10) }])));
+
+    // 16 is newlink as a request, but the reply to getlink from the kernel
+    let mut cursor = Cursor::new(raw_data);
+    cursor.set_position(16);
+    let m = match specs.read(&mut cursor, family, 16, 0, raw_data.len()) {
+        Some(NlMsgEnum::Spec(m)) => m,
+        m => panic!("expected a Spec message, not {:?}", m),
+    };
+    assert!(m.op == "getlink");
+    assert!(m.ifindex() == Some(5));
+
+    // not a message that the spec has, and 18 is only a request
+    let mut cursor = Cursor::new(raw_data);
+    assert!(specs.read(&mut cursor, family, 20, request, raw_data.len()).is_none());
+    assert!(specs.read(&mut cursor, family, 18, 0, raw_data.len()).is_none());
+    assert!(cursor.position() == 0);
+}
+
+#[test]
+fn test_Family_from_yaml_bad_values() {
+    // a bit that doesn't fit in a u64, a negative one and an attribute
+    // that doesn't fit in a u16 are left out instead of panicking
+    let y = yaml::parse("
+name: bad
+protocol: netlink-raw
+protonum: 0
+definitions:
+  -
+    name: big-flags
+    type: flags
+    entries:
+      - name: low
+      - name: huge
+        value: 64
+      - name: negative
+        value: -1
+      - name: high
+        value: 63
+attribute-sets:
+  -
+    name: attrs
+    attributes:
+      - name: ok
+        type: u32
+      - name: too-big
+        type: u32
+        value: 65536
+      - name: negative
+        type: u32
+        value: -1
+      - name: last
+        type: u32
+        value: 65535
+").unwrap();
+    let f = Family::from_yaml(&y).unwrap();
+    let e = &f.enums["big-flags"];
+    assert!(e.entries == vec![("low".to_string(), 1), ("high".to_string(), 1 << 63)]);
+    let attrs: Vec<(&str, u16)> = f.attr_sets["attrs"].iter()
+                                                       .map(|a| (&a.name[..], a.value))
+                                                       .collect();
+    assert!(attrs == vec![("ok", 1), ("last", 65535)]);
+}
+
+#[test]
+fn test_SpecSet_add_kernel_specs() {
+    let mut specs = SpecSet::new();
+    specs.add(yaml::RT_LINK_EXCERPT).unwrap();
+    specs.add(yaml::NETDEV_EXCERPT).unwrap();
+    specs.add(yaml::ETHTOOL_EXCERPT).unwrap();
+    let names: Vec<&str> = specs.families().iter().map(|f| &f.name[..]).collect();
+    assert!(names == vec!["rt-link", "netdev", "ethtool"]);
+
+    let rt_link = &specs.families()[0];
+    assert!(rt_link.protocol == Protocol::NetlinkRaw);
+    assert!(rt_link.enums["ifinfo-flags"].name_of(1 << 18) == Some("echo"));
+    assert!(rt_link.enums["vlan-protocols"].name_of(0x8100) == Some("8021q"));
+    let linkinfo = rt_link.attr_sets["link-attrs"].iter().find(|a| a.name == "linkinfo");
+    assert!(linkinfo.map(|a| a.value) == Some(18));
+    let requests: Vec<(u16, &str)> = rt_link.requests.iter().map(|m| (m.value, &m.op[..]))
+                                                         .collect();
+    assert!(requests == vec![(16, "newlink"), (18, "getlink")]);
+    let replies: Vec<(u16, &str)> = rt_link.replies.iter().map(|m| (m.value, &m.op[..]))
+                                                       .collect();
+    assert!(replies == vec![(16, "getlink")]);
+
+    let netdev = &specs.families()[1];
+    assert!(netdev.protocol == Protocol::Genetlink);
+    assert!(netdev.enums["xdp-act"].flag_names(0x41) == "basic|ndo-xmit-sg");
+    // the unified enum-model uses the same values both ways
+    assert!(netdev.requests.iter().map(|m| m.value).collect::<Vec<u16>>() == vec![1, 2, 3, 4]);
+    assert!(netdev.replies.iter().map(|m| m.value).collect::<Vec<u16>>() == vec![1, 2, 3, 4]);
+
+    let ethtool = &specs.families()[2];
+    assert!(ethtool.enums["udp-tunnel-type"].name_of(2) == Some("vxlan-gpe"));
+    assert!(ethtool.enums["header-flags"].flag_names(6) == "omit-reply|stats");
+}
@@ -0,0 +1,408 @@
+/* Netlink buffers that were logged as text instead of captured. strace prints
+ * the buffers of sendmsg() and friends as C strings (use -xx to get them all
+ * in hex) and plenty of programs log a hex dump of what they sent. Neither
+ * has a cooked header, so the caller has to know the netlink family.
+ */
+
+use ::std::io;
+use ::std::io::prelude::*;
+use ::std::fmt;
+
+// The syscalls that carry netlink buffers, and which way they go
+const SYSCALLS: [(&'static str, Direction); 4] = [("sendmsg", Direction::Request),
+                                                  ("sendto", Direction::Request),
+                                                  ("recvmsg", Direction::Reply),
+                                                  ("recvfrom", Direction::Reply)];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Direction {
+    /// Sent to the kernel: sendmsg() or sendto()
+    Request,
+    /// Received from the kernel: recvmsg() or recvfrom()
+    Reply,
+}
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Direction::Request => write!(f, "request"),
+            Direction::Reply => write!(f, "reply"),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextFormat {
+    Strace,
+    Hex,
+}
+
+/// One netlink buffer pulled out of the text
+#[derive(Debug, Default, Clone)]
+pub struct TextBuffer {
+    /// The line that the buffer started on
+    pub line: usize,
+    /// The syscall that strace saw, eg. "recvmsg"
+    pub syscall: Option<String>,
+    pub direction: Option<Direction>,
+    /// The timestamp strace printed with -t, -tt or -ttt, as it was printed
+    pub timestamp: Option<String>,
+    /// strace cut the buffer off (-s was too small)
+    pub truncated: bool,
+    pub data: Vec<u8>,
+}
+impl TextBuffer {
+    /// The -ttt timestamp as seconds and nanoseconds, if that is what we have
+    pub fn epoch(&self) -> Option<(u64, u32)> {
+        let ts = match self.timestamp {
+            Some(ref ts) => ts,
+            None => return None,
+        };
+        let mut parts = ts.splitn(2, '.');
+        let sec = match parts.next().map(|s| s.parse::<u64>()) {
+            Some(Ok(sec)) => sec,
+            _ => return None,
+        };
+        let frac = parts.next().unwrap_or("");
+        if frac.len() > 9 || !frac.chars().all(|c| c.is_digit(10)) {
+            return None;
+        }
+        let nsec = format!("{:0<9}", frac).parse::<u32>().unwrap_or(0);
+        Some((sec, nsec))
+    }
+}
+
+pub struct TextReader {
+    r: Box<BufRead>,
+    format: TextFormat,
+    line: usize,
+}
+impl TextReader {
+    pub fn new(r: Box<BufRead>, format: TextFormat) -> TextReader {
+        TextReader { r: r, format: format, line: 0 }
+    }
+    /// The next buffer, or None at the end of the input
+    pub fn next(&mut self) -> io::Result<Option<TextBuffer>> {
+        match self.format {
+            TextFormat::Strace => self.next_strace(),
+            TextFormat::Hex => self.next_hex(),
+        }
+    }
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut buf = Vec::new();
+        if try!(self.r.read_until(b'\n', &mut buf)) == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        Ok(Some(String::from_utf8_lossy(&buf).into_owned()))
+    }
+    /// Every strace line with a netlink syscall and a buffer in it is one
+    /// buffer. Everything else is skipped.
+    fn next_strace(&mut self) -> io::Result<Option<TextBuffer>> {
+        loop {
+            let line = match try!(self.read_line()) {
+                Some(l) => l,
+                None => return Ok(None),
+            };
+            match parse_strace_line(&line) {
+                Some(mut b) => {
+                    b.line = self.line;
+                    return Ok(Some(b));
+                }
+                None => continue,
+            }
+        }
+    }
+    /// A hex dump runs until the next blank line (or a line with no hex).
+    fn next_hex(&mut self) -> io::Result<Option<TextBuffer>> {
+        let mut b = TextBuffer::default();
+        loop {
+            let line = match try!(self.read_line()) {
+                Some(l) => l,
+                None => break,
+            };
+            match parse_hex_line(&line) {
+                Some(bytes) => {
+                    if b.data.is_empty() {
+                        b.line = self.line;
+                    }
+                    b.data.extend_from_slice(&bytes);
+                }
+                None => {
+                    if !b.data.is_empty() {
+                        break;
+                    }
+                }
+            }
+        }
+        if b.data.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(b))
+    }
+}
+
+/// Find the netlink syscall in an strace line. Returns the syscall, where
+/// its arguments start and whether this is the second half of a call that
+/// another thread interrupted.
+fn find_syscall(line: &str) -> Option<(&'static str, Direction, usize, bool)> {
+    let mut found: Option<(&'static str, Direction, usize, bool)> = None;
+    for &(name, dir) in SYSCALLS.iter() {
+        let call = format!("{}(", name);
+        let resumed = format!("<... {} resumed>", name);
+        let mut hits = Vec::new();
+        if let Some(i) = line.find(&*call) {
+            // don't match the end of some other word, eg. __sendmsg(
+            let prev = line[..i].chars().last();
+            if prev.map(|c| !c.is_alphanumeric() && c != '_').unwrap_or(true) {
+                hits.push((i, false));
+            }
+        }
+        if let Some(i) = line.find(&*resumed) {
+            hits.push((i, true));
+        }
+        for (i, r) in hits {
+            if found.map(|f| i < f.2).unwrap_or(true) {
+                found = Some((name, dir, i, r));
+            }
+        }
+    }
+    found
+}
+
+/// strace puts the pid and the timestamp in front of the syscall:
+/// "[pid  1234] 12:34:56.789012 sendmsg(" or "1234  1452822917.386625 recvmsg("
+fn parse_timestamp(prefix: &str) -> Option<String> {
+    for t in prefix.split_whitespace() {
+        if t.starts_with('[') || t.ends_with(']') || t == "[pid" {
+            continue;
+        }
+        if t.contains('.') || t.contains(':') {
+            if t.chars().all(|c| c.is_digit(10) || c == '.' || c == ':') {
+                return Some(String::from(t));
+            }
+        }
+    }
+    None
+}
+
+/// The return value at the end of the line, eg. "= 1234" or "= -1 EAGAIN"
+fn parse_return(line: &str) -> Option<i64> {
+    let i = match line.rfind(") = ") {
+        Some(i) => i + 4,
+        None => match line.rfind("> = ") {
+            Some(i) => i + 4,
+            None => return None,
+        },
+    };
+    line[i..].split_whitespace().next().and_then(|s| s.parse::<i64>().ok())
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'...b'9' => Some(c - b'0'),
+        b'a'...b'f' => Some(c - b'a' + 10),
+        b'A'...b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decode one C string starting just after its opening quote. Returns the
+/// bytes and where the closing quote was.
+fn parse_c_string(s: &[u8]) -> (Vec<u8>, usize) {
+    let mut v = Vec::new();
+    let mut i = 0;
+    while i < s.len() {
+        match s[i] {
+            b'"' => return (v, i),
+            b'\\' if i + 1 < s.len() => {
+                i += 1;
+                match s[i] {
+                    b'x' => {
+                        let mut n = 0u8;
+                        let mut digits = 0;
+                        while digits < 2 && i + 1 < s.len() {
+                            match hex_digit(s[i + 1]) {
+                                Some(d) => n = n << 4 | d,
+                                None => break,
+                            }
+                            i += 1;
+                            digits += 1;
+                        }
+                        v.push(n);
+                    }
+                    b'0'...b'7' => {
+                        let mut n = (s[i] - b'0') as u32;
+                        let mut digits = 1;
+                        while digits < 3 && i + 1 < s.len() && s[i + 1] >= b'0' && s[i + 1] <= b'7' {
+                            i += 1;
+                            n = n << 3 | (s[i] - b'0') as u32;
+                            digits += 1;
+                        }
+                        v.push(n as u8);
+                    }
+                    b'n' => v.push(b'\n'),
+                    b't' => v.push(b'\t'),
+                    b'r' => v.push(b'\r'),
+                    b'v' => v.push(0x0b),
+                    b'f' => v.push(0x0c),
+                    b'a' => v.push(0x07),
+                    b'b' => v.push(0x08),
+                    c => v.push(c),
+                }
+            }
+            c => v.push(c),
+        }
+        i += 1;
+    }
+    (v, s.len())
+}
+
+fn parse_strace_line(line: &str) -> Option<TextBuffer> {
+    let (name, dir, start, _) = match find_syscall(line) {
+        Some(s) => s,
+        None => return None,
+    };
+    let ret = parse_return(line);
+    // the call failed, so there is nothing in the buffer worth reading
+    if ret.map(|r| r < 0).unwrap_or(false) {
+        return None;
+    }
+
+    let mut b = TextBuffer::default();
+    b.syscall = Some(String::from(name));
+    b.direction = Some(dir);
+    b.timestamp = parse_timestamp(&line[..start]);
+
+    // All of the strings are buffers: one for sendto()/recvfrom() and one
+    // per iovec for sendmsg()/recvmsg().
+    let bytes = line.as_bytes();
+    let mut i = start;
+    while i < bytes.len() {
+        if bytes[i] == b'"' {
+            let (s, end) = parse_c_string(&bytes[i + 1..]);
+            b.data.extend_from_slice(&s);
+            i = i + 1 + end + 1;
+            if line[i.min(line.len())..].starts_with("...") {
+                b.truncated = true;
+            }
+        }
+        else {
+            i += 1;
+        }
+    }
+    // The receive buffer is usually bigger than what the kernel put in it
+    if dir == Direction::Reply {
+        if let Some(r) = ret {
+            b.data.truncate(r as usize);
+        }
+    }
+    if b.data.is_empty() {
+        return None;
+    }
+    Some(b)
+}
+
+fn parse_hex_token(t: &str) -> Option<Vec<u8>> {
+    let t = t.trim_end_matches(',');
+    let t = if t.starts_with("0x") || t.starts_with("0X") { &t[2..] } else { t };
+    if t.is_empty() || t.len() % 2 != 0 {
+        return None;
+    }
+    let b = t.as_bytes();
+    let mut v = Vec::new();
+    for pair in b.chunks(2) {
+        match (hex_digit(pair[0]), hex_digit(pair[1])) {
+            (Some(h), Some(l)) => v.push(h << 4 | l),
+            _ => return None,
+        }
+    }
+    Some(v)
+}
+
+/// Pull the bytes out of one line of `hexdump -C`, `xxd`, `od -An -tx1` or
+/// plain hex. A leading offset or "label:" is skipped, as is the ASCII column.
+fn parse_hex_line(line: &str) -> Option<Vec<u8>> {
+    // hexdump -C puts the ASCII between |s
+    let line = match line.find('|') {
+        Some(i) => &line[..i],
+        None => line,
+    };
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let mut skip = 0;
+    if tokens.len() > 1 {
+        // "00000010:", "recv:" or the 8 digit offset that hexdump -C uses.
+        // A long first token followed by bytes is an offset, but followed by
+        // another long one it is the first of some 32 bit words.
+        if tokens[0].ends_with(':') ||
+           (tokens[0].len() >= 6 && tokens[1].len() == 2 &&
+            parse_hex_token(tokens[1]).is_some()) {
+            skip = 1;
+        }
+    }
+    let mut v = Vec::new();
+    for t in tokens[skip..].iter() {
+        match parse_hex_token(t) {
+            Some(bytes) => v.extend_from_slice(&bytes),
+            // the ASCII column of xxd
+            None => break,
+        }
+    }
+    if v.is_empty() {
+        return None;
+    }
+    Some(v)
+}
+
+#[test]
+fn test_parse_strace_line() {
+    let b = parse_strace_line("1452822917.386625 sendto(3, \"\\x14\\x00\\x00\\x00\\x12\\x00\\x01\\x03\", 8, 0, \
+                               {sa_family=AF_NETLINK, nl_pid=0, nl_groups=00000000}, 12) = 8\n").unwrap();
+    assert!(b.syscall == Some(String::from("sendto")));
+    assert!(b.direction == Some(Direction::Request));
+    assert!(b.epoch() == Some((1452822917, 386625000)));
+    assert!(b.data == vec![0x14, 0, 0, 0, 0x12, 0, 1, 3]);
+
+    // two iovecs, cut down to what the kernel sent, octal and plain chars
+    let b = parse_strace_line("[pid  42] recvmsg(3, {msg_name={sa_family=AF_NETLINK, nl_pid=0, \
+                               nl_groups=00000000}, msg_namelen=12, msg_iov=[{iov_base=\"\\1\\2a\", \
+                               iov_len=3}, {iov_base=\"\\x04\\x05\"..., iov_len=8192}], msg_iovlen=2, \
+                               msg_controllen=0, msg_flags=0}, 0) = 4").unwrap();
+    assert!(b.direction == Some(Direction::Reply));
+    assert!(b.timestamp.is_none());
+    assert!(b.truncated);
+    assert!(b.data == vec![1, 2, b'a', 4]);
+
+    let b = parse_strace_line("<... recvfrom resumed> \"\\x01\", 4096, 0, NULL, NULL) = 1").unwrap();
+    assert!(b.syscall == Some(String::from("recvfrom")));
+    assert!(b.data == vec![1]);
+
+    assert!(parse_strace_line("recvmsg(3,  <unfinished ...>").is_none());
+    assert!(parse_strace_line("recvmsg(3, {msg_iov=[{iov_base=\"\", iov_len=0}]}, 0) = -1 EAGAIN \
+                               (Resource temporarily unavailable)").is_none());
+    assert!(parse_strace_line("write(1, \"\\x01\", 1) = 1").is_none());
+}
+
+#[test]
+fn test_parse_hex_line() {
+    assert!(parse_hex_line("00000000  14 00 00 00 12 00 01 03  |........|\n") ==
+            Some(vec![0x14, 0, 0, 0, 0x12, 0, 1, 3]));
+    assert!(parse_hex_line("00000010: 1400 0000 1200  ......") == Some(vec![0x14, 0, 0, 0, 0x12, 0]));
+    assert!(parse_hex_line("recv: 0x14, 0x00,") == Some(vec![0x14, 0]));
+    assert!(parse_hex_line("1400000012000103") == Some(vec![0x14, 0, 0, 0, 0x12, 0, 1, 3]));
+    assert!(parse_hex_line("14000000 12000103") == Some(vec![0x14, 0, 0, 0, 0x12, 0, 1, 3]));
+    assert!(parse_hex_line("") == None);
+    assert!(parse_hex_line("netlink message:") == None);
+}
+
+#[test]
+fn test_textreader_hex() {
+    let text = "first\n14 00 00 00\n12 00 01 03\n\n\nab cd\n";
+    let mut r = TextReader::new(Box::new(io::Cursor::new(text.as_bytes().to_vec())), TextFormat::Hex);
+    let b = r.next().unwrap().unwrap();
+    assert!(b.line == 2);
+    assert!(b.data == vec![0x14, 0, 0, 0, 0x12, 0, 1, 3]);
+    let b = r.next().unwrap().unwrap();
+    assert!(b.line == 6);
+    assert!(b.data == vec![0xab, 0xcd]);
+    assert!(r.next().unwrap().is_none());
+}
@@ -0,0 +1,1125 @@
+//! Just enough YAML for the netlink specs that ship with the kernel, in
+//! Documentation/netlink/specs: block mappings and sequences, flow
+//! sequences and mappings, plain and quoted scalars, block scalars, anchors
+//! and aliases. There are no tags, no multiple documents and no complex
+//! keys, because the specs don't use them.
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Yaml {
+    Null,
+    Str(String),
+    Seq(Vec<Yaml>),
+    Map(Vec<(String, Yaml)>),
+}
+impl Yaml {
+    /// The value of `key`, if this is a mapping that has it
+    pub fn get(&self, key: &str) -> Option<&Yaml> {
+        match *self {
+            Yaml::Map(ref m) => m.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref v)| v),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Yaml::Str(ref s) => Some(s),
+            _ => None,
+        }
+    }
+    /// The items of a sequence. Anything else has none.
+    pub fn as_seq(&self) -> &[Yaml] {
+        match *self {
+            Yaml::Seq(ref v) => v,
+            _ => &[],
+        }
+    }
+    /// A decimal or 0x hex integer
+    pub fn as_i64(&self) -> Option<i64> {
+        let s = match self.as_str() {
+            Some(s) => s.trim(),
+            None => return None,
+        };
+        let (neg, s) = if s.starts_with('-') { (true, &s[1..]) } else { (false, s) };
+        let n = if s.starts_with("0x") || s.starts_with("0X") {
+            i64::from_str_radix(&s[2..], 16).ok()
+        }
+        else {
+            s.parse::<i64>().ok()
+        };
+        n.map(|n| if neg { -n } else { n })
+    }
+    /// Shorthand for get(key) then as_str()
+    pub fn str_of(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(|v| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub line: usize,
+    pub msg: String,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+pub fn parse(s: &str) -> Result<Yaml, Error> {
+    let mut p = Parser {
+        lines: s.lines().map(|l| l.replace('\t', " ")).collect(),
+        pos: 0,
+        anchors: HashMap::new(),
+    };
+    let y = try!(p.parse_node(0));
+    p.skip_blank();
+    if p.pos < p.lines.len() {
+        return Err(p.error("unexpected indentation"));
+    }
+    Ok(y)
+}
+
+fn indent(l: &str) -> usize {
+    l.len() - l.trim_start_matches(' ').len()
+}
+
+fn is_blank(l: &str) -> bool {
+    let t = l.trim();
+    t.is_empty() || t.starts_with('#') || t == "---" || t.starts_with('%')
+}
+
+fn is_seq_item(t: &str) -> bool {
+    t == "-" || t.starts_with("- ")
+}
+
+/// Cut off a comment, which starts with a # after whitespace, outside quotes
+fn strip_comment(s: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) => if c == q { quote = None },
+            None => {
+                if (c == '"' || c == '\'') && (prev == ' ' || i == 0) {
+                    quote = Some(c);
+                }
+                else if c == '#' && (prev == ' ' || i == 0) {
+                    return s[..i].trim_end();
+                }
+            }
+        }
+        prev = c;
+    }
+    s.trim_end()
+}
+
+/// Where the colon after a mapping key is
+fn find_key_colon(t: &str) -> Option<usize> {
+    if t.starts_with('[') || t.starts_with('{') || t.starts_with('&') || t.starts_with('*') {
+        return None;
+    }
+    let mut start = 0;
+    if t.starts_with('"') || t.starts_with('\'') {
+        match t[1..].find(&t[..1]) {
+            Some(end) => start = end + 2,
+            None => return None,
+        }
+    }
+    let b = t.as_bytes();
+    for i in start..b.len() {
+        if b[i] == b'#' && i > 0 && b[i - 1] == b' ' {
+            return None;
+        }
+        if b[i] == b':' && (i + 1 == b.len() || b[i + 1] == b' ') {
+            return Some(i);
+        }
+    }
+    None
+}
+
+fn unquote(s: &str) -> String {
+    if s.len() >= 2 && s.starts_with('\'') && s.ends_with('\'') {
+        return s[1..s.len() - 1].replace("''", "'");
+    }
+    if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+        let mut out = String::new();
+        let mut chars = s[1..s.len() - 1].chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(c) => out.push(c),
+                None => (),
+            }
+        }
+        return out;
+    }
+    s.to_string()
+}
+
+fn quote_closed(s: &str) -> bool {
+    let q = &s[..1];
+    let rest = &s[1..];
+    if q == "'" {
+        // '' is an escaped quote
+        rest.replace("''", "").contains('\'')
+    }
+    else {
+        rest.replace("\\\\", "").replace("\\\"", "").contains('"')
+    }
+}
+
+fn flow_closed(s: &str) -> bool {
+    let mut depth = 0;
+    let mut quote = None;
+    for c in s.chars() {
+        match quote {
+            Some(q) => if c == q { quote = None },
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                _ => (),
+            },
+        }
+    }
+    depth <= 0
+}
+
+struct Flow<'a> {
+    s: &'a [char],
+    i: usize,
+}
+impl<'a> Flow<'a> {
+    fn skip_ws(&mut self) {
+        while self.i < self.s.len() && self.s[self.i] == ' ' {
+            self.i += 1;
+        }
+    }
+    fn peek(&self) -> Option<char> {
+        self.s.get(self.i).cloned()
+    }
+    fn scalar(&mut self) -> Result<String, String> {
+        self.skip_ws();
+        let start = self.i;
+        match self.peek() {
+            Some(q @ '"') | Some(q @ '\'') => {
+                self.i += 1;
+                while self.i < self.s.len() {
+                    if self.s[self.i] == '\\' && q == '"' {
+                        self.i += 1;
+                    }
+                    else if self.s[self.i] == q {
+                        if q == '\'' && self.s.get(self.i + 1) == Some(&'\'') {
+                            self.i += 1;
+                        }
+                        else {
+                            break;
+                        }
+                    }
+                    self.i += 1;
+                }
+                if self.i >= self.s.len() {
+                    return Err("unterminated quote".to_string());
+                }
+                self.i += 1;
+                let s: String = self.s[start..self.i].iter().cloned().collect();
+                Ok(unquote(&s))
+            }
+            _ => {
+                while let Some(c) = self.peek() {
+                    if c == ',' || c == ']' || c == '}' ||
+                       (c == ':' && self.s.get(self.i + 1).map_or(true, |n| *n == ' ')) {
+                        break;
+                    }
+                    self.i += 1;
+                }
+                if self.i == start {
+                    // eg. a : or a ] where we wanted a value
+                    return match self.peek() {
+                        Some(c) => Err(format!("unexpected {}", c)),
+                        None => Err("expected a value".to_string()),
+                    };
+                }
+                let s: String = self.s[start..self.i].iter().cloned().collect();
+                Ok(s.trim().to_string())
+            }
+        }
+    }
+    fn node(&mut self) -> Result<Yaml, String> {
+        self.skip_ws();
+        match self.peek() {
+            Some('[') => {
+                self.i += 1;
+                let mut v = Vec::new();
+                loop {
+                    self.skip_ws();
+                    match self.peek() {
+                        Some(']') => { self.i += 1; break; }
+                        Some(',') => { self.i += 1; continue; }
+                        None => return Err("unterminated [".to_string()),
+                        _ => v.push(try!(self.node())),
+                    }
+                }
+                Ok(Yaml::Seq(v))
+            }
+            Some('{') => {
+                self.i += 1;
+                let mut m = Vec::new();
+                loop {
+                    self.skip_ws();
+                    match self.peek() {
+                        Some('}') => { self.i += 1; break; }
+                        Some(',') => { self.i += 1; continue; }
+                        None => return Err("unterminated {".to_string()),
+                        _ => {
+                            let k = try!(self.scalar());
+                            self.skip_ws();
+                            let v = if self.peek() == Some(':') {
+                                self.i += 1;
+                                try!(self.node())
+                            }
+                            else {
+                                Yaml::Null
+                            };
+                            m.push((k, v));
+                        }
+                    }
+                }
+                Ok(Yaml::Map(m))
+            }
+            _ => Ok(Yaml::Str(try!(self.scalar()))),
+        }
+    }
+}
+
+struct Parser {
+    lines: Vec<String>,
+    pos: usize,
+    anchors: HashMap<String, Yaml>,
+}
+impl Parser {
+    fn error(&self, msg: &str) -> Error {
+        Error { line: self.pos + 1, msg: msg.to_string() }
+    }
+    fn skip_blank(&mut self) {
+        while self.pos < self.lines.len() && is_blank(&self.lines[self.pos]) {
+            self.pos += 1;
+        }
+    }
+    /// Parse whatever starts on the next line, if it is indented at least
+    /// `min_indent`
+    fn parse_node(&mut self, min_indent: usize) -> Result<Yaml, Error> {
+        self.skip_blank();
+        if self.pos >= self.lines.len() {
+            return Ok(Yaml::Null);
+        }
+        let ind = indent(&self.lines[self.pos]);
+        if ind < min_indent {
+            return Ok(Yaml::Null);
+        }
+        let text = self.lines[self.pos][ind..].to_string();
+        if is_seq_item(&text) {
+            self.parse_seq(ind)
+        }
+        else if find_key_colon(&text).is_some() {
+            self.parse_map(ind)
+        }
+        else {
+            // a scalar on a line of its own goes on for as long as the lines
+            // are indented more than its parent, which is less than `ind`
+            self.parse_value(ind.saturating_sub(1), &text)
+        }
+    }
+    fn parse_seq(&mut self, ind: usize) -> Result<Yaml, Error> {
+        let mut v = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.pos >= self.lines.len() || indent(&self.lines[self.pos]) != ind {
+                break;
+            }
+            let rest = {
+                let t = &self.lines[self.pos][ind..];
+                if !is_seq_item(t) {
+                    break;
+                }
+                t[1..].to_string()
+            };
+            let item = strip_comment(rest.trim_start());
+            if item.is_empty() {
+                self.pos += 1;
+                v.push(try!(self.parse_node(ind + 1)));
+            }
+            else {
+                // parse what follows the - as if it started a line of its own
+                let col = ind + 1 + rest.len() - rest.trim_start().len();
+                self.lines[self.pos] = format!("{}{}", " ".repeat(col), rest.trim_start());
+                v.push(try!(self.parse_node(col)));
+            }
+        }
+        Ok(Yaml::Seq(v))
+    }
+    fn parse_map(&mut self, ind: usize) -> Result<Yaml, Error> {
+        let mut m = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.pos >= self.lines.len() || indent(&self.lines[self.pos]) != ind {
+                break;
+            }
+            let (key, rest) = {
+                let t = &self.lines[self.pos][ind..];
+                if is_seq_item(t) {
+                    break;
+                }
+                let c = match find_key_colon(t) {
+                    Some(c) => c,
+                    None => return Err(self.error("expected a key")),
+                };
+                (unquote(t[..c].trim()), t[c + 1..].to_string())
+            };
+            let v = try!(self.parse_value(ind, &rest));
+            m.push((key, v));
+        }
+        Ok(Yaml::Map(m))
+    }
+    /// Parse a value that starts at `text`, the rest of the current line,
+    /// for a key or item that is indented `ind`.
+    fn parse_value(&mut self, ind: usize, text: &str) -> Result<Yaml, Error> {
+        let mut text = strip_comment(text).trim().to_string();
+        let mut anchor = None;
+        if text.starts_with('&') {
+            let end = text.find(' ').unwrap_or(text.len());
+            anchor = Some(text[1..end].to_string());
+            text = text[end..].trim().to_string();
+        }
+        let v = if text.is_empty() {
+            self.pos += 1;
+            self.skip_blank();
+            if self.pos >= self.lines.len() {
+                Yaml::Null
+            }
+            else {
+                let next = indent(&self.lines[self.pos]);
+                if next > ind {
+                    try!(self.parse_node(next))
+                }
+                else if next == ind && is_seq_item(&self.lines[self.pos][next..]) {
+                    // a sequence can be at the same indent as its key
+                    try!(self.parse_seq(ind))
+                }
+                else {
+                    Yaml::Null
+                }
+            }
+        }
+        else if text.starts_with('*') {
+            let v = match self.anchors.get(&text[1..]) {
+                Some(v) => v.clone(),
+                None => return Err(self.error("unknown alias")),
+            };
+            self.pos += 1;
+            v
+        }
+        else if text.starts_with('|') || text.starts_with('>') {
+            self.pos += 1;
+            self.block_scalar(ind, text.starts_with('>'))
+        }
+        else if text.starts_with('[') || text.starts_with('{') {
+            // flow collections can go over several lines
+            let mut s = text.clone();
+            self.pos += 1;
+            while !flow_closed(&s) && self.pos < self.lines.len() {
+                s.push(' ');
+                s.push_str(strip_comment(self.lines[self.pos].trim()));
+                self.pos += 1;
+            }
+            let chars: Vec<char> = s.chars().collect();
+            let mut flow = Flow { s: &chars, i: 0 };
+            match flow.node() {
+                Ok(v) => v,
+                Err(e) => return Err(Error { line: self.pos, msg: e }),
+            }
+        }
+        else if text.starts_with('"') || text.starts_with('\'') {
+            let mut s = text.clone();
+            self.pos += 1;
+            while !quote_closed(&s) && self.pos < self.lines.len() {
+                s.push(' ');
+                s.push_str(self.lines[self.pos].trim());
+                self.pos += 1;
+            }
+            Yaml::Str(unquote(strip_comment(&s)))
+        }
+        else {
+            // plain scalars go on for as long as the lines are indented more
+            let mut s = text.clone();
+            self.pos += 1;
+            loop {
+                self.skip_blank();
+                if self.pos >= self.lines.len() || indent(&self.lines[self.pos]) <= ind {
+                    break;
+                }
+                s.push(' ');
+                s.push_str(strip_comment(self.lines[self.pos].trim()));
+                self.pos += 1;
+            }
+            Yaml::Str(s)
+        };
+        if let Some(a) = anchor {
+            self.anchors.insert(a, v.clone());
+        }
+        Ok(v)
+    }
+    fn block_scalar(&mut self, ind: usize, folded: bool) -> Yaml {
+        let mut v: Vec<String> = Vec::new();
+        let mut content_indent = None;
+        while self.pos < self.lines.len() {
+            let l = &self.lines[self.pos];
+            if l.trim().is_empty() {
+                v.push(String::new());
+                self.pos += 1;
+                continue;
+            }
+            let i = indent(l);
+            if i <= ind {
+                break;
+            }
+            let ci = *content_indent.get_or_insert(i);
+            v.push(l[::std::cmp::min(ci, i)..].to_string());
+            self.pos += 1;
+        }
+        while v.last().map_or(false, |l| l.is_empty()) {
+            v.pop();
+        }
+        Yaml::Str(v.join(if folded { " " } else { "\n" }))
+    }
+}
+
+#[test]
+fn test_parse() {
+    let y = parse("
+# a comment
+name: rt-link   # another
+protonum: 0
+doc: a long line
+  that goes on
+definitions:
+  -
+    name: ifinfomsg
+    type: struct
+    members:
+      - name: ifi-family
+        type: u8
+      -
+        name: pad
+        len: 1
+  - name: flags
+    entries: [ up, 'broad cast', \"x\" ]
+list:
+- a
+- b
+anchored: &ids [ 1, 2 ]
+alias: *ids
+text: |
+  first
+    second
+map: { a: 1, b: [ c ] }
+").unwrap();
+    assert!(y.str_of("name") == Some("rt-link"));
+    assert!(y.get("protonum").unwrap().as_i64() == Some(0));
+    assert!(y.str_of("doc") == Some("a long line that goes on"));
+    let defs = y.get("definitions").unwrap().as_seq();
+    assert!(defs.len() == 2);
+    assert!(defs[0].str_of("type") == Some("struct"));
+    let members = defs[0].get("members").unwrap().as_seq();
+    assert!(members.len() == 2);
+    assert!(members[0].str_of("name") == Some("ifi-family"));
+    assert!(members[1].get("len").unwrap().as_i64() == Some(1));
+    let entries = defs[1].get("entries").unwrap().as_seq();
+    assert!(entries == &[Yaml::Str("up".to_string()), Yaml::Str("broad cast".to_string()),
+                         Yaml::Str("x".to_string())]);
+    assert!(y.get("list").unwrap().as_seq().len() == 2);
+    assert!(y.get("alias") == y.get("anchored"));
+    assert!(y.str_of("text") == Some("first\n  second"));
+    assert!(y.get("map").unwrap().get("b").unwrap().as_seq().len() == 1);
+
+    assert!(parse("a: [ 1, 2\nb: 3\n").is_err());
+    assert!(parse("just a string\n").unwrap() == Yaml::Str("just a string".to_string()));
+}
+
+/// The start of Documentation/netlink/specs/rt-link.yaml, with most of the
+/// attributes and operations left out
+#[cfg(test)]
+pub const RT_LINK_EXCERPT: &'static str = "
+# SPDX-License-Identifier: ((GPL-2.0 WITH Linux-syscall-note) OR BSD-3-Clause)
+
+name: rt-link
+protocol: netlink-raw
+protonum: 0
+
+doc:
+  Link configuration over rtnetlink.
+
+definitions:
+  -
+    name: ifinfo-flags
+    type: flags
+    entries:
+      -
+        name: up
+      -
+        name: broadcast
+      -
+        name: debug
+      -
+        name: loopback
+      -
+        name: point-to-point
+      -
+        name: no-trailers
+      -
+        name: running
+      -
+        name: no-arp
+      -
+        name: promisc
+      -
+        name: all-multi
+      -
+        name: master
+      -
+        name: slave
+      -
+        name: multicast
+      -
+        name: portsel
+      -
+        name: auto-media
+      -
+        name: dynamic
+      -
+        name: lower-up
+      -
+        name: dormant
+      -
+        name: echo
+  -
+    name: vlan-protocols
+    type: enum
+    entries:
+      -
+        name: 8021q
+        value: 0x8100
+      -
+        name: 8021ad
+        value: 0x88a8
+  -
+    name: rtgenmsg
+    type: struct
+    members:
+      -
+        name: family
+        type: u8
+  -
+    name: ifinfomsg
+    type: struct
+    members:
+      -
+        name: ifi-family
+        type: u8
+      -
+        name: pad
+        type: pad
+        len: 1
+      -
+        name: ifi-type
+        type: u16
+      -
+        name: ifi-index
+        type: s32
+      -
+        name: ifi-flags
+        type: u32
+        enum: ifinfo-flags
+        enum-as-flags: true
+      -
+        name: ifi-change
+        type: u32
+  -
+    name: vlan-flags
+    type: flags
+    entries:
+      - reorder-hdr
+      - gvrp
+      - loose-binding
+      - mvrp
+      - bridge-binding
+  -
+    name: ifla-vlan-flags
+    type: struct
+    members:
+      -
+        name: flags
+        type: u32
+        enum: vlan-flags
+        enum-as-flags: true
+      -
+        name: mask
+        type: u32
+        display-hint: hex
+
+attribute-sets:
+  -
+    name: link-attrs
+    name-prefix: ifla-
+    attributes:
+      -
+        name: address
+        type: binary
+        display-hint: mac
+      -
+        name: broadcast
+        type: binary
+        display-hint: mac
+      -
+        name: ifname
+        type: string
+      -
+        name: mtu
+        type: u32
+      -
+        name: link
+        type: u32
+      -
+        name: qdisc
+        type: string
+      -
+        name: stats
+        type: binary
+        struct: rtnl-link-stats
+      -
+        name: cost
+        type: string
+      -
+        name: priority
+        type: string
+      -
+        name: master
+        type: u32
+      -
+        name: wireless
+        type: string
+      -
+        name: protinfo
+        type: string
+      -
+        name: txqlen
+        type: u32
+      -
+        name: map
+        type: binary
+        struct: rtnl-link-ifmap
+      -
+        name: weight
+        type: u32
+      -
+        name: operstate
+        type: u8
+      -
+        name: linkmode
+        type: u8
+      -
+        name: linkinfo
+        type: nest
+        nested-attributes: linkinfo-attrs
+  -
+    name: linkinfo-attrs
+    name-prefix: ifla-info-
+    attributes:
+      -
+        name: kind
+        type: string
+      -
+        name: data
+        type: sub-message
+        sub-message: linkinfo-data-msg
+        selector: kind
+      -
+        name: xstats
+        type: binary
+      -
+        name: slave-kind
+        type: string
+      -
+        name: slave-data
+        type: sub-message
+        sub-message: linkinfo-member-data-msg
+        selector: slave-kind
+  -
+    name: linkinfo-vlan-attrs
+    name-prefix: ifla-vlan-
+    attributes:
+      -
+        name: id
+        type: u16
+      -
+        name: flags
+        type: binary
+        struct: ifla-vlan-flags
+      -
+        name: egress-qos
+        type: nest
+        nested-attributes: ifla-vlan-qos
+      -
+        name: ingress-qos
+        type: nest
+        nested-attributes: ifla-vlan-qos
+      -
+        name: protocol
+        type: u16
+        enum: vlan-protocols
+        byte-order: big-endian
+
+sub-messages:
+  -
+    name: linkinfo-data-msg
+    formats:
+      -
+        value: bridge
+        attribute-set: linkinfo-bridge-attrs
+      -
+        value: vlan
+        attribute-set: linkinfo-vlan-attrs
+
+operations:
+  enum-model: directional
+  list:
+    -
+      name: newlink
+      doc: Create a new link.
+      attribute-set: link-attrs
+      fixed-header: ifinfomsg
+      do:
+        request:
+          value: 16
+          attributes: &link-new-attrs
+            - ifi-index
+            - ifname
+            - linkinfo
+            - address
+            - broadcast
+            - mtu
+            - txqlen
+            - operstate
+            - linkmode
+    -
+      name: getlink
+      doc: Get / dump information about a link.
+      attribute-set: link-attrs
+      fixed-header: ifinfomsg
+      do:
+        request:
+          value: 18
+          attributes:
+            - ifi-index
+            - ifname
+        reply:
+          value: 16
+          attributes: &link-all-attrs
+            - ifi-family
+            - ifi-type
+            - ifi-index
+            - ifi-flags
+            - ifi-change
+            - address
+            - ifname
+            - mtu
+      dump:
+        request:
+          attributes:
+            - ifi-family
+            - master
+            - linkinfo
+        reply:
+          value: 16
+          attributes: *link-all-attrs
+
+mcast-groups:
+  list:
+    -
+      name: rtnlgrp-link
+      value: 1
+";
+
+/// Parts of Documentation/netlink/specs/netdev.yaml, a genetlink family
+#[cfg(test)]
+pub const NETDEV_EXCERPT: &'static str = "
+# SPDX-License-Identifier: ((GPL-2.0 WITH Linux-syscall-note) OR BSD-3-Clause)
+
+name: netdev
+
+doc:
+  netdev configuration over generic netlink.
+
+definitions:
+  -
+    type: flags
+    name: xdp-act
+    render-max: true
+    entries:
+      -
+        name: basic
+        doc:
+          XDP features set supported by all drivers
+          (XDP_ABORTED, XDP_DROP, XDP_PASS, XDP_TX)
+      -
+        name: redirect
+        doc:
+          The netdev supports XDP_REDIRECT
+      -
+        name: ndo-xmit
+        doc:
+          This feature informs if netdev implements ndo_xdp_xmit callback.
+      -
+        name: xsk-zerocopy
+        doc:
+          This feature informs if netdev supports AF_XDP in zero copy mode.
+      -
+        name: hw-offload
+        doc:
+         This feature informs if netdev supports XDP hw offloading.
+      -
+        name: rx-sg
+        doc:
+          This feature informs if netdev implements non-linear XDP buffer
+          support in the driver napi callback.
+      -
+        name: ndo-xmit-sg
+        doc:
+          This feature informs if netdev implements non-linear XDP buffer
+          support in ndo_xdp_xmit callback.
+  -
+    type: flags
+    name: xdp-rx-metadata
+    entries:
+      -
+        name: timestamp
+        doc: |
+          Device is capable of exposing receive HW timestamp via bpf_xdp_metadata_rx_timestamp().
+      -
+        name: hash
+        doc: |
+          Device is capable of exposing receive packet hash via bpf_xdp_metadata_rx_hash().
+
+attribute-sets:
+  -
+    name: dev
+    attributes:
+      -
+        name: ifindex
+        doc: netdev ifindex
+        type: u32
+        checks:
+          min: 1
+      -
+        name: pad
+        type: pad
+      -
+        name: xdp-features
+        doc: Bitmask of enabled xdp-features.
+        type: u64
+        enum: xdp-act
+      -
+        name: xdp-zc-max-segs
+        doc: max fragment count supported by ZC driver
+        type: u32
+        checks:
+          min: 1
+      -
+        name: xdp-rx-metadata-features
+        doc: Bitmask of supported XDP receive metadata features.
+             See Documentation/networking/xdp-rx-metadata.rst for more details.
+        type: u64
+        enum: xdp-rx-metadata
+
+operations:
+  list:
+    -
+      name: dev-get
+      doc: Get / dump information about a netdev.
+      attribute-set: dev
+      do:
+        request:
+          attributes:
+            - ifindex
+        reply: &dev-all
+          attributes:
+            - ifindex
+            - xdp-features
+            - xdp-zc-max-segs
+            - xdp-rx-metadata-features
+      dump:
+        reply: *dev-all
+    -
+      name: dev-add-ntf
+      doc: Notification about device appearing.
+      notify: dev-get
+      mcgrp: mgmt
+    -
+      name: dev-del-ntf
+      doc: Notification about device disappearing.
+      notify: dev-get
+      mcgrp: mgmt
+    -
+      name: dev-change-ntf
+      doc: Notification about device configuration being changed.
+      notify: dev-get
+      mcgrp: mgmt
+
+mcast-groups:
+  list:
+    -
+      name: mgmt
+
+kernel-family:
+  headers: [ \"linux/list.h\" ]
+";
+
+/// The definitions at the start of Documentation/netlink/specs/ethtool.yaml,
+/// which use flow sequences
+#[cfg(test)]
+pub const ETHTOOL_EXCERPT: &'static str = "
+name: ethtool
+
+protocol: genetlink-legacy
+
+doc: Partial family for Ethtool Netlink.
+
+definitions:
+  -
+    name: udp-tunnel-type
+    enum-name:
+    type: enum
+    entries: [ vxlan, geneve, vxlan-gpe ]
+  -
+    name: stringset
+    type: enum
+    entries: []
+  -
+    name: header-flags
+    type: flags
+    entries: [ compact-bitsets, omit-reply, stats ]
+
+attribute-sets:
+  -
+    name: header
+    attributes:
+      -
+        name: dev-index
+        type: u32
+      -
+        name: dev-name
+        type: string
+      -
+        name: flags
+        type: u32
+        enum: header-flags
+
+operations:
+  enum-model: directional
+  list:
+    -
+      name: strset-get
+      doc: Get string set from the kernel.
+
+      attribute-set: strset
+
+      do: &strset-get-op
+        request:
+          value: 1
+          attributes:
+            - header
+            - stringsets
+            - counts-only
+        reply:
+          value: 1
+          attributes:
+            - header
+            - stringsets
+      dump: *strset-get-op
+";
+
+#[test]
+fn test_parse_rt_link() {
+    let y = parse(RT_LINK_EXCERPT).unwrap();
+    assert!(y.str_of("name") == Some("rt-link"));
+    assert!(y.str_of("doc") == Some("Link configuration over rtnetlink."));
+    let defs = y.get("definitions").unwrap().as_seq();
+    assert!(defs.len() == 6);
+    assert!(defs[0].get("entries").unwrap().as_seq().len() == 19);
+    let vlan_protocols = defs[1].get("entries").unwrap().as_seq();
+    assert!(vlan_protocols[1].str_of("name") == Some("8021ad"));
+    assert!(vlan_protocols[1].get("value").unwrap().as_i64() == Some(0x88a8));
+    assert!(defs[4].get("entries").unwrap().as_seq()[2].as_str() == Some("loose-binding"));
+
+    let sets = y.get("attribute-sets").unwrap().as_seq();
+    assert!(sets.len() == 3);
+    let link = sets[0].get("attributes").unwrap().as_seq();
+    assert!(link.len() == 18);
+    assert!(link[17].str_of("nested-attributes") == Some("linkinfo-attrs"));
+
+    let ops = y.get("operations").unwrap();
+    assert!(ops.str_of("enum-model") == Some("directional"));
+    let getlink = &ops.get("list").unwrap().as_seq()[1];
+    assert!(getlink.str_of("doc") == Some("Get / dump information about a link."));
+    let all = getlink.get("do").unwrap().get("reply").unwrap().get("attributes").unwrap();
+    assert!(all.as_seq().len() == 8);
+    let dump_reply = getlink.get("dump").unwrap().get("reply").unwrap();
+    assert!(dump_reply.get("value").unwrap().as_i64() == Some(16));
+    assert!(dump_reply.get("attributes") == Some(all));
+}
+
+#[test]
+fn test_parse_genetlink() {
+    let y = parse(NETDEV_EXCERPT).unwrap();
+    assert!(y.str_of("doc") == Some("netdev configuration over generic netlink."));
+    let defs = y.get("definitions").unwrap().as_seq();
+    let xdp_act = defs[0].get("entries").unwrap().as_seq();
+    assert!(xdp_act.len() == 7);
+    assert!(xdp_act[0].str_of("doc") ==
+            Some("XDP features set supported by all drivers (XDP_ABORTED, XDP_DROP, XDP_PASS, XDP_TX)"));
+    assert!(xdp_act[4].str_of("doc") ==
+            Some("This feature informs if netdev supports XDP hw offloading."));
+    let metadata = defs[1].get("entries").unwrap().as_seq();
+    assert!(metadata[1].str_of("doc") ==
+            Some("Device is capable of exposing receive packet hash via bpf_xdp_metadata_rx_hash()."));
+    let dev = y.get("attribute-sets").unwrap().as_seq()[0].get("attributes").unwrap().as_seq();
+    assert!(dev[0].get("checks").unwrap().get("min").unwrap().as_i64() == Some(1));
+    assert!(dev[4].str_of("doc") ==
+            Some("Bitmask of supported XDP receive metadata features. \
+                  See Documentation/networking/xdp-rx-metadata.rst for more details."));
+    let ops = y.get("operations").unwrap().get("list").unwrap().as_seq();
+    assert!(ops.len() == 4);
+    assert!(ops[0].get("dump").unwrap().get("reply") ==
+            ops[0].get("do").unwrap().get("reply"));
+    assert!(ops[3].str_of("mcgrp") == Some("mgmt"));
+    let headers = y.get("kernel-family").unwrap().get("headers").unwrap();
+    assert!(headers.as_seq() == &[Yaml::Str("linux/list.h".to_string())]);
+
+    let y = parse(ETHTOOL_EXCERPT).unwrap();
+    let defs = y.get("definitions").unwrap().as_seq();
+    assert!(defs[0].get("enum-name") == Some(&Yaml::Null));
+    assert!(defs[0].get("entries").unwrap().as_seq() ==
+            &[Yaml::Str("vxlan".to_string()), Yaml::Str("geneve".to_string()),
+              Yaml::Str("vxlan-gpe".to_string())]);
+    assert!(defs[1].get("entries").unwrap().as_seq().is_empty());
+    let op = &y.get("operations").unwrap().get("list").unwrap().as_seq()[0];
+    assert!(op.str_of("attribute-set") == Some("strset"));
+    assert!(op.get("dump") == op.get("do"));
+}